@@ -0,0 +1,301 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::settings::Settings;
+use crate::{despawn_screen, spawn_button, ButtonSize, Despawn, GameState};
+
+// what the game reacts to; keys, mouse buttons and gamepad buttons are bound to these in the settings file,
+// and everything reads ButtonInput<Action> instead of the devices
+pub struct ActionsPlugin;
+impl Plugin for ActionsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ButtonInput<Action>>()
+            .init_resource::<Rebinding>()
+            .add_systems(PreUpdate, update_actions.after(bevy::input::InputSystem))
+            // the rebinding screen
+            .add_systems(OnEnter(GameState::Controls), setup_controls)
+            .add_systems(
+                Update,
+                (capture_binding, controls_buttons, update_controls)
+                    .chain()
+                    .run_if(in_state(GameState::Controls)),
+            )
+            .add_systems(OnExit(GameState::Controls), despawn_screen);
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum Action {
+    Jump,
+    Duck,
+    Pause,
+    // start, play again
+    Confirm,
+    // leave a screen, quit from the menu
+    Back,
+    Mute,
+    DebugKill,
+    DebugSkipCameraStep,
+    DebugFlipCamera,
+}
+impl Action {
+    pub const ALL: [Action; 9] = [
+        Action::Jump,
+        Action::Duck,
+        Action::Pause,
+        Action::Confirm,
+        Action::Back,
+        Action::Mute,
+        Action::DebugKill,
+        Action::DebugSkipCameraStep,
+        Action::DebugFlipCamera,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Jump => "Jump",
+            Action::Duck => "Duck",
+            Action::Pause => "Pause",
+            Action::Confirm => "Confirm",
+            Action::Back => "Back",
+            Action::Mute => "Mute",
+            Action::DebugKill => "Debug: die",
+            Action::DebugSkipCameraStep => "Debug: next camera step",
+            Action::DebugFlipCamera => "Debug: flip camera",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    // on any connected gamepad
+    Gamepad(GamepadButton),
+}
+impl Binding {
+    pub fn name(self) -> String {
+        match self {
+            Binding::Key(key) => format!("{key:?}"),
+            Binding::Mouse(button) => format!("Mouse {button:?}"),
+            Binding::Gamepad(button) => format!("Pad {button:?}"),
+        }
+    }
+
+    // a new binding only replaces the ones from the same kind of device
+    fn same_device(self, other: Binding) -> bool {
+        std::mem::discriminant(&self) == std::mem::discriminant(&other)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(transparent)]
+pub struct Bindings(BTreeMap<Action, Vec<Binding>>);
+impl Default for Bindings {
+    fn default() -> Self {
+        use Binding::{Gamepad as Pad, Key, Mouse};
+        Bindings(BTreeMap::from([
+            (Action::Jump, vec![Key(KeyCode::Space), Key(KeyCode::ArrowUp), Mouse(MouseButton::Left), Pad(GamepadButton::South)]),
+            (Action::Duck, vec![Key(KeyCode::ArrowDown), Pad(GamepadButton::DPadDown)]),
+            (Action::Pause, vec![Key(KeyCode::Escape), Key(KeyCode::KeyP), Pad(GamepadButton::Start)]),
+            (Action::Confirm, vec![Key(KeyCode::Space), Key(KeyCode::Enter), Pad(GamepadButton::South)]),
+            (Action::Back, vec![Key(KeyCode::Escape), Pad(GamepadButton::East)]),
+            (Action::Mute, vec![Key(KeyCode::KeyM)]),
+            (Action::DebugKill, vec![Key(KeyCode::KeyQ)]),
+            (Action::DebugSkipCameraStep, vec![Key(KeyCode::KeyR)]),
+            (Action::DebugFlipCamera, vec![Key(KeyCode::KeyS)]),
+        ]))
+    }
+}
+impl Bindings {
+    pub fn get(&self, action: Action) -> &[Binding] {
+        self.0.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn rebind(&mut self, action: Action, binding: Binding) {
+        let bindings = self.0.entry(action).or_default();
+        bindings.retain(|old| !old.same_device(binding));
+        bindings.push(binding);
+    }
+
+    // actions the file doesn't mention get their default bindings
+    pub fn fill_defaults(&mut self) {
+        for (action, bindings) in Bindings::default().0 {
+            self.0.entry(action).or_insert(bindings);
+        }
+    }
+}
+
+// waiting for the next press to bind it to this action
+#[derive(Resource, Default)]
+struct Rebinding(Option<Action>);
+
+pub(crate) fn update_actions(
+    mut actions: ResMut<ButtonInput<Action>>,
+    settings: Res<Settings>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    gamepads: Query<&Gamepad>,
+) {
+    actions.clear();
+    for action in Action::ALL {
+        let pressed = settings.file.bindings.get(action).iter().any(|binding| match *binding {
+            Binding::Key(key) => keys.pressed(key),
+            Binding::Mouse(button) => mouse.pressed(button),
+            Binding::Gamepad(button) => gamepads.iter().any(|gamepad| gamepad.pressed(button)),
+        });
+        if pressed {
+            actions.press(action);
+        } else {
+            actions.release(action);
+        }
+    }
+}
+
+#[derive(Component)]
+enum ControlsButton {
+    Rebind(Action),
+    Defaults,
+    Back,
+}
+
+// the bindings of an action, kept up to date
+#[derive(Component)]
+struct BindingsText(Action);
+
+fn setup_controls(mut commands: Commands, mut rebinding: ResMut<Rebinding>) {
+    rebinding.0 = None;
+    commands
+    .spawn((Node {
+        width: Val::Percent(100.0),
+        height: Val::Percent(100.0),
+        flex_direction: FlexDirection::Column,
+        align_items: AlignItems::Center,
+        justify_content: JustifyContent::Center,
+        ..default()
+    }, Despawn))
+    .with_children(|parent| {
+        parent.spawn((
+            Text::new("Controls (click one, then press the new button)"),
+            TextColor(Color::srgb(0.9, 0.9, 0.9)),
+        ));
+        for action in Action::ALL {
+            parent.spawn(Node {
+                align_items: AlignItems::Center,
+                ..default()
+            })
+            .with_children(|row| {
+                spawn_button(
+                    row,
+                    ButtonSize::Small,
+                    Val::Px(260.0),
+                    Text::new(action.name()),
+                    ControlsButton::Rebind(action),
+                );
+                row.spawn((
+                    Node {
+                        width: Val::Px(420.0),
+                        ..default()
+                    },
+                    Text::default(),
+                    TextFont::from_font_size(16.),
+                    TextColor(Color::srgb(0.6, 0.6, 0.6)),
+                    BindingsText(action),
+                ));
+            });
+        }
+        parent.spawn(Node::default()).with_children(|row| {
+            spawn_button(row, ButtonSize::Small, Val::Auto, Text::new("Defaults"), ControlsButton::Defaults);
+            spawn_button(row, ButtonSize::Small, Val::Auto, Text::new("Back"), ControlsButton::Back);
+        });
+    });
+}
+
+// the first press after a rebind button was clicked; runs before the buttons, so the click itself isn't it
+fn capture_binding(
+    mut rebinding: ResMut<Rebinding>,
+    mut settings: ResMut<Settings>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    gamepads: Query<&Gamepad>,
+) {
+    let Some(action) = rebinding.0 else { return };
+    let pressed = keys
+        .get_just_pressed()
+        .next()
+        .map(|key| Binding::Key(*key))
+        .or_else(|| mouse.get_just_pressed().next().map(|button| Binding::Mouse(*button)))
+        .or_else(|| {
+            gamepads
+                .iter()
+                .find_map(|gamepad| gamepad.get_just_pressed().next().map(|button| Binding::Gamepad(*button)))
+        });
+    let Some(binding) = pressed else { return };
+    settings.file.bindings.rebind(action, binding);
+    settings.write();
+    rebinding.0 = None;
+}
+
+fn controls_buttons(
+    interaction_query: Query<(&Interaction, &ControlsButton), Changed<Interaction>>,
+    mut rebinding: ResMut<Rebinding>,
+    mut settings: ResMut<Settings>,
+    actions: Res<ButtonInput<Action>>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    for (interaction, button) in &interaction_query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match button {
+            ControlsButton::Rebind(action) => rebinding.0 = Some(*action),
+            ControlsButton::Defaults => {
+                settings.file.bindings = Bindings::default();
+                settings.write();
+                rebinding.0 = None;
+            }
+            ControlsButton::Back => game_state.set(GameState::Menu),
+        }
+    }
+    // while waiting for a press, back is just another button to bind
+    if rebinding.0.is_none() && actions.just_pressed(Action::Back) {
+        game_state.set(GameState::Menu);
+    }
+}
+
+fn update_controls(
+    rebinding: Res<Rebinding>,
+    settings: Res<Settings>,
+    mut query: Query<(&mut Text, &BindingsText)>,
+) {
+    for (mut text, BindingsText(action)) in &mut query {
+        text.0 = if rebinding.0 == Some(*action) {
+            "press something...".to_string()
+        } else {
+            let names: Vec<String> = settings.file.bindings.get(*action).iter().map(|binding| binding.name()).collect();
+            names.join(", ")
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rebinding_replaces_the_same_device() {
+        let mut bindings = Bindings::default();
+        bindings.rebind(Action::Jump, Binding::Key(KeyCode::KeyW));
+        assert_eq!(
+            bindings.get(Action::Jump),
+            [
+                Binding::Mouse(MouseButton::Left),
+                Binding::Gamepad(GamepadButton::South),
+                Binding::Key(KeyCode::KeyW)
+            ]
+        );
+        bindings.rebind(Action::DebugKill, Binding::Gamepad(GamepadButton::Select));
+        assert_eq!(bindings.get(Action::DebugKill).len(), 2);
+    }
+}
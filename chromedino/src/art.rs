@@ -0,0 +1,176 @@
+use bevy::prelude::*;
+use bevy::sprite::{Anchor, SpritePlugin};
+
+use crate::collision::Hitbox;
+use crate::ghost::Ghost;
+use crate::score::RunScore;
+use crate::{Dino, GameState, ObstacleKind};
+
+// relative to the assets folder
+const DINO_FILE: &str = "sprites/dino.png";
+const CACTUS_FILE: &str = "sprites/cactus.png";
+const FLYER_FILE: &str = "sprites/flyer.png";
+
+// dino.png is one row of cells: two running, standing (for the jump), two ducking, dead
+const DINO_CELL: UVec2 = UVec2::new(16, 30);
+const DINO_FRAMES: u32 = 6;
+const RUN_FRAMES: [usize; 2] = [0, 1];
+const JUMP_FRAME: usize = 2;
+const DUCK_FRAMES: [usize; 2] = [3, 4];
+const DEAD_FRAME: usize = 5;
+// drawn at twice the pixels, a bit wider than the hitbox; a duck is drawn in the bottom half of a cell
+const DINO_SPRITE_SIZE: Vec2 = Vec2::new(32., 60.);
+// the legs switch every this many pixels scrolled, so they keep up with the speed
+const RUN_FRAME_PIXELS: f32 = 40.;
+
+// flyer.png is two cells, wings up and down
+const FLYER_CELL: UVec2 = UVec2::new(20, 12);
+const FLAP_SECONDS: f32 = 0.15;
+
+// cactus.png is stretched to any size: the corners stay, the sides and the middle repeat
+const CACTUS_BORDER: f32 = 4.;
+
+// swaps the plain rectangles for sprites from the atlases; the hitboxes don't change, only what's drawn.
+// the sprites are white and tinted with the palette. headless the rectangles stay
+pub struct ArtPlugin;
+impl Plugin for ArtPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<SpritePlugin>() {
+            return;
+        }
+        app.add_systems(Startup, load_art)
+            .add_systems(Update, (dress_dino, dress_ghost, dress_obstacles, animate_dino, animate_ghost, animate_flyers).chain());
+    }
+}
+
+#[derive(Resource)]
+struct Art {
+    dino: Handle<Image>,
+    dino_layout: Handle<TextureAtlasLayout>,
+    cactus: Handle<Image>,
+    flyer: Handle<Image>,
+    flyer_layout: Handle<TextureAtlasLayout>,
+}
+
+fn load_art(mut commands: Commands, asset_server: Res<AssetServer>, mut layouts: ResMut<Assets<TextureAtlasLayout>>) {
+    commands.insert_resource(Art {
+        dino: asset_server.load(DINO_FILE),
+        dino_layout: layouts.add(TextureAtlasLayout::from_grid(DINO_CELL, DINO_FRAMES, 1, None, None)),
+        cactus: asset_server.load(CACTUS_FILE),
+        flyer: asset_server.load(FLYER_FILE),
+        flyer_layout: layouts.add(TextureAtlasLayout::from_grid(FLYER_CELL, 2, 1, None, None)),
+    });
+}
+
+fn dress_dino(art: Res<Art>, mut query: Query<&mut Sprite, Added<Dino>>) {
+    for mut sprite in &mut query {
+        dress_as_dino(&art, &mut sprite);
+    }
+}
+
+// the ghost is drawn the same, only see-through
+fn dress_ghost(art: Res<Art>, mut query: Query<&mut Sprite, Added<Ghost>>) {
+    for mut sprite in &mut query {
+        dress_as_dino(&art, &mut sprite);
+    }
+}
+
+fn dress_as_dino(art: &Art, sprite: &mut Sprite) {
+    sprite.image = art.dino.clone();
+    sprite.texture_atlas = Some(TextureAtlas {
+        layout: art.dino_layout.clone(),
+        index: RUN_FRAMES[0],
+    });
+    sprite.custom_size = Some(DINO_SPRITE_SIZE);
+}
+
+fn dress_obstacles(art: Res<Art>, mut query: Query<(&mut Sprite, &ObstacleKind), Added<ObstacleKind>>) {
+    for (mut sprite, kind) in &mut query {
+        match kind {
+            ObstacleKind::Ground => {
+                sprite.image = art.cactus.clone();
+                sprite.image_mode = SpriteImageMode::Sliced(TextureSlicer {
+                    border: BorderRect::square(CACTUS_BORDER),
+                    center_scale_mode: SliceScaleMode::Tile { stretch_value: 1. },
+                    sides_scale_mode: SliceScaleMode::Tile { stretch_value: 1. },
+                    max_corner_scale: 1.,
+                });
+            }
+            ObstacleKind::Flying => {
+                sprite.image = art.flyer.clone();
+                sprite.texture_atlas = Some(TextureAtlas {
+                    layout: art.flyer_layout.clone(),
+                    index: 0,
+                });
+            }
+        }
+    }
+}
+
+// which cell of dino.png to draw
+fn dino_frame(dino: &Dino, dead: bool, distance: f32) -> usize {
+    let step = (distance / RUN_FRAME_PIXELS) as usize % 2;
+    if dead {
+        DEAD_FRAME
+    } else if dino.jumped {
+        JUMP_FRAME
+    } else if dino.ducking {
+        DUCK_FRAMES[step]
+    } else {
+        RUN_FRAMES[step]
+    }
+}
+
+fn animate_dino(
+    mut query: Query<(&Dino, &Hitbox, &mut Sprite)>,
+    game_state: Res<State<GameState>>,
+    run_score: Res<RunScore>,
+) {
+    for (dino, hitbox, mut sprite) in &mut query {
+        animate_as_dino(&mut sprite, dino, hitbox, *game_state == GameState::Dead, run_score.distance);
+    }
+}
+
+fn animate_ghost(mut query: Query<(&Ghost, &Hitbox, &mut Sprite)>) {
+    for (ghost, hitbox, mut sprite) in &mut query {
+        animate_as_dino(&mut sprite, &ghost.dino, hitbox, ghost.dead, ghost.distance);
+    }
+}
+
+fn animate_as_dino(sprite: &mut Sprite, dino: &Dino, hitbox: &Hitbox, dead: bool, distance: f32) {
+    if let Some(atlas) = &mut sprite.texture_atlas {
+        atlas.index = dino_frame(dino, dead, distance);
+    }
+    // the bottom of the sprite stays on the bottom of the hitbox when it shrinks for a duck
+    sprite.anchor = Anchor::Custom(Vec2::new(0., (hitbox.size().y - DINO_SPRITE_SIZE.y) / 2. / DINO_SPRITE_SIZE.y));
+}
+
+fn animate_flyers(mut query: Query<(&ObstacleKind, &mut Sprite)>, time: Res<Time>) {
+    let index = (time.elapsed_secs() / FLAP_SECONDS) as usize % 2;
+    for (kind, mut sprite) in &mut query {
+        if *kind != ObstacleKind::Flying {
+            continue;
+        }
+        if let Some(atlas) = &mut sprite.texture_atlas {
+            atlas.index = index;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frames_follow_the_dino() {
+        let mut dino = Dino::default();
+        assert_eq!(dino_frame(&dino, false, 0.), RUN_FRAMES[0]);
+        assert_eq!(dino_frame(&dino, false, RUN_FRAME_PIXELS), RUN_FRAMES[1]);
+        dino.ducking = true;
+        assert_eq!(dino_frame(&dino, false, 0.), DUCK_FRAMES[0]);
+        dino.ducking = false;
+        dino.jumped = true;
+        assert_eq!(dino_frame(&dino, false, 0.), JUMP_FRAME);
+        assert_eq!(dino_frame(&dino, true, 0.), DEAD_FRAME);
+    }
+}
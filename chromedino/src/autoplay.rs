@@ -0,0 +1,378 @@
+use bevy::prelude::*;
+use std::io::{self, BufRead, Write};
+
+use crate::actions::Action;
+use crate::collision::Hitbox;
+use crate::patterns::{JumpArc, JUMP_MARGIN_HEIGHT, JUMP_MARGIN_TICKS};
+use crate::score::RunScore;
+use crate::tuning::Tuning;
+use crate::{
+    despawn_screen, Dino, DinoInput, DinoInputSet, GameManager, GameState, GameplaySet, InRun,
+    Obstacle, ObstacleKind, Position, RunTick, DINO_HEIGHT, DINO_WIDTH, PLAIN_HEIGHT, TICKS_PER_SECOND,
+};
+
+const AUTOPLAY_ARG: &str = "--autoplay";
+// idle this long on the menu and the demo starts
+const DEMO_IDLE_SECONDS: f32 = 15.;
+
+// something other than the keyboard playing: --autoplay rule (or stdio) plays every run,
+// and the rule agent plays a demo behind the menu when nobody touches anything for a while
+pub struct AutoplayPlugin;
+impl Plugin for AutoplayPlugin {
+    fn build(&self, app: &mut App) {
+        let agent = agent_from_args();
+        if let Some(agent) = &agent {
+            info!("{} is playing", agent.name());
+        }
+        app.insert_resource(Autopilot {
+            agent,
+            demo: Box::new(RuleAgent),
+        })
+        .init_resource::<MenuIdle>()
+        .add_systems(OnEnter(InRun), reset_agents)
+        .add_systems(
+            FixedUpdate,
+            drive_dino
+                .in_set(DinoInputSet)
+                .run_if(in_state(GameState::Demo).or(in_state(GameState::Game).and(has_agent))),
+        )
+        .add_systems(OnEnter(GameState::Dead), report_agent_run.run_if(has_agent))
+        // demo
+        .add_systems(OnEnter(GameState::Menu), reset_menu_idle)
+        .add_systems(Update, start_demo.run_if(in_state(GameState::Menu)))
+        .add_systems(Update, leave_demo.run_if(in_state(GameState::Demo)))
+        .add_systems(FixedUpdate, end_demo_on_death.after(GameplaySet).run_if(in_state(GameState::Demo)))
+        .add_systems(
+            OnTransition {
+                exited: GameState::Demo,
+                entered: GameState::Menu,
+            },
+            despawn_screen,
+        );
+    }
+}
+
+// an obstacle as an agent sees it, in pixels from the dino's center along the ground
+#[derive(Clone, PartialEq, Debug)]
+pub struct SeenObstacle {
+    pub kind: ObstacleKind,
+    pub left: f32,
+    pub right: f32,
+    // above the ground
+    pub bottom: f32,
+    pub top: f32,
+}
+
+// everything an agent gets to decide on
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct AgentView {
+    pub tick: u32,
+    // 0 to 1, like GameManager
+    pub game_speed: f32,
+    // pixels per second the obstacles come in at
+    pub scroll_speed: f32,
+    // of the dino's feet above the ground
+    pub height: f32,
+    pub velocity: f32,
+    pub jumped: bool,
+    pub ducking: bool,
+    // the ones the dino hasn't passed yet, closest first
+    pub obstacles: Vec<SeenObstacle>,
+}
+
+// reads the view off the world, none without a dino
+pub fn observe(world: &mut World) -> Option<AgentView> {
+    let (dino, position) = world.query::<(&Dino, &Position)>().get_single(world).ok()?;
+    let height = position.y - dino.size().y / 2. - PLAIN_HEIGHT;
+    let (velocity, jumped, ducking) = (dino.velocity, dino.jumped, dino.ducking);
+
+    let mut obstacles: Vec<SeenObstacle> = world
+        .query_filtered::<(&Position, &Hitbox, &ObstacleKind), With<Obstacle>>()
+        .iter(world)
+        .map(|(position, hitbox, kind)| {
+            // the box around it, a rounded flyer looks square from here
+            let bounds = hitbox.bounds();
+            SeenObstacle {
+                kind: *kind,
+                left: position.x + bounds.min.x,
+                right: position.x + bounds.max.x,
+                bottom: position.y + bounds.min.y - PLAIN_HEIGHT,
+                top: position.y + bounds.max.y - PLAIN_HEIGHT,
+            }
+        })
+        .filter(|obstacle| obstacle.right >= -DINO_WIDTH / 2.)
+        .collect();
+    obstacles.sort_by(|a, b| a.left.total_cmp(&b.left));
+
+    let game_speed = world.resource::<GameManager>().game_speed;
+    Some(AgentView {
+        tick: **world.resource::<RunTick>(),
+        game_speed,
+        scroll_speed: world.resource::<Tuning>().speed.scroll_speed(game_speed),
+        height,
+        velocity,
+        jumped,
+        ducking,
+        obstacles,
+    })
+}
+
+// decides the dino's input for each tick
+pub trait Agent: Send + Sync {
+    // for the log
+    fn name(&self) -> &str;
+    // a new run is starting
+    fn reset(&mut self) {}
+    fn act(&mut self, view: &AgentView, tuning: &Tuning) -> DinoInput;
+}
+
+// jumps every group of obstacles as early as it can while still clearing it, the same way patterns.rs checks them,
+// so anything the generator lays out it gets over
+pub struct RuleAgent;
+impl Agent for RuleAgent {
+    fn name(&self) -> &str {
+        "rule agent"
+    }
+
+    fn act(&mut self, view: &AgentView, tuning: &Tuning) -> DinoInput {
+        let hold = DinoInput { jump_held: true, ..default() };
+        if view.jumped {
+            return hold;
+        }
+        let arc = JumpArc::new(&tuning.jump);
+        let speed = view.scroll_speed;
+        let margin = JUMP_MARGIN_TICKS * speed / TICKS_PER_SECOND as f32;
+        let dino_left = -DINO_WIDTH / 2.;
+
+        // the flyers it can run under are left alone, the rest get jumped, a few at a time if they're too close for a landing
+        let mut blockers = view.obstacles.iter().filter(|obstacle| obstacle.bottom < DINO_HEIGHT);
+        let Some(first) = blockers.next() else { return hold };
+        let (mut right, mut top) = (first.right, first.top);
+        let takeoff = |right: f32, top: f32| {
+            arc.above(top + JUMP_MARGIN_HEIGHT).map(|(_, down)| right + margin - speed * down)
+        };
+        for next in blockers {
+            let Some(earliest) = takeoff(right, top) else { break };
+            let landed = earliest.max(dino_left) + speed * arc.airtime() + margin;
+            if landed <= next.left - DINO_WIDTH - margin {
+                break;
+            }
+            right = right.max(next.right);
+            top = top.max(next.top);
+        }
+
+        let jump = takeoff(right, top).is_some_and(|earliest| dino_left >= earliest);
+        DinoInput { jump, ..hold }
+    }
+}
+
+// plays a fixed list of inputs: each one from its tick until the next, a jump press only on its own tick
+pub struct ScriptedAgent {
+    steps: Vec<(u32, DinoInput)>,
+}
+impl ScriptedAgent {
+    pub fn new(mut steps: Vec<(u32, DinoInput)>) -> Self {
+        steps.sort_by_key(|(tick, _)| *tick);
+        ScriptedAgent { steps }
+    }
+}
+impl Agent for ScriptedAgent {
+    fn name(&self) -> &str {
+        "scripted agent"
+    }
+
+    fn act(&mut self, view: &AgentView, _tuning: &Tuning) -> DinoInput {
+        let current = self.steps.partition_point(|(tick, _)| *tick <= view.tick);
+        let Some(&(tick, input)) = current.checked_sub(1).map(|i| &self.steps[i]) else {
+            return DinoInput::default();
+        };
+        DinoInput { jump: input.jump && tick == view.tick, ..input }
+    }
+}
+
+// another program plays: every tick a line goes out on stdout
+//   tick game_speed scroll_speed height velocity jumped ducking obstacle_count (left right bottom top)...
+// and a line comes back on stdin with any of the words jump (press), hold (jump held) and duck.
+// the game waits for the answer, so it runs as fast as the other side can keep up
+pub struct StdioAgent;
+impl Agent for StdioAgent {
+    fn name(&self) -> &str {
+        "stdio agent"
+    }
+
+    fn act(&mut self, view: &AgentView, _tuning: &Tuning) -> DinoInput {
+        let mut line = format!(
+            "{} {} {} {} {} {} {} {}",
+            view.tick,
+            view.game_speed,
+            view.scroll_speed,
+            view.height,
+            view.velocity,
+            view.jumped as u8,
+            view.ducking as u8,
+            view.obstacles.len()
+        );
+        for obstacle in &view.obstacles {
+            line += &format!(" {} {} {} {}", obstacle.left, obstacle.right, obstacle.bottom, obstacle.top);
+        }
+        let mut stdout = io::stdout().lock();
+        if let Err(e) = writeln!(stdout, "{line}").and_then(|_| stdout.flush()) {
+            warn!("couldn't write to the agent: {e}");
+            return DinoInput::default();
+        }
+
+        let mut answer = String::new();
+        if let Err(e) = io::stdin().lock().read_line(&mut answer) {
+            warn!("couldn't read from the agent: {e}");
+        }
+        let has = |word: &str| answer.split_whitespace().any(|w| w == word);
+        DinoInput {
+            jump: has("jump"),
+            jump_held: has("jump") || has("hold"),
+            duck: has("duck"),
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct Autopilot {
+    // plays the runs started from the menu, the keyboard does when there's none
+    pub agent: Option<Box<dyn Agent>>,
+    pub demo: Box<dyn Agent>,
+}
+
+// seconds since anything was pressed on the menu
+#[derive(Resource, Default)]
+struct MenuIdle(f32);
+
+fn agent_from_args() -> Option<Box<dyn Agent>> {
+    let value = crate::arg_value(AUTOPLAY_ARG)?;
+    match value.as_str() {
+        "rule" => Some(Box::new(RuleAgent)),
+        "stdio" => Some(Box::new(StdioAgent)),
+        _ => {
+            warn!("unknown agent {value:?}, try rule or stdio");
+            None
+        }
+    }
+}
+
+fn has_agent(autopilot: Res<Autopilot>) -> bool {
+    autopilot.agent.is_some()
+}
+
+fn reset_agents(mut autopilot: ResMut<Autopilot>) {
+    if let Some(agent) = &mut autopilot.agent {
+        agent.reset();
+    }
+    autopilot.demo.reset();
+}
+
+// replays record what it decided
+pub(crate) fn drive_dino(world: &mut World) {
+    let Some(view) = observe(world) else { return };
+    let demo = *world.resource::<State<GameState>>() == GameState::Demo;
+    let input = world.resource_scope(|world, mut autopilot: Mut<Autopilot>| {
+        let tuning = world.resource::<Tuning>();
+        match (&mut autopilot.agent, demo) {
+            (Some(agent), false) => agent.act(&view, tuning),
+            _ => autopilot.demo.act(&view, tuning),
+        }
+    });
+    *world.resource_mut::<DinoInput>() = input;
+}
+
+// for soak tests and benchmarks
+fn report_agent_run(autopilot: Res<Autopilot>, run_score: Res<RunScore>) {
+    if let Some(agent) = &autopilot.agent {
+        info!("{} scored {} in {:.1}s", agent.name(), run_score.score(), run_score.seconds);
+    }
+}
+
+fn reset_menu_idle(mut idle: ResMut<MenuIdle>) {
+    idle.0 = 0.;
+}
+
+// actions cover the gamepad
+fn anything_pressed(keys: &ButtonInput<KeyCode>, mouse: &ButtonInput<MouseButton>, actions: &ButtonInput<Action>) -> bool {
+    keys.get_just_pressed().next().is_some()
+        || mouse.get_just_pressed().next().is_some()
+        || actions.get_just_pressed().next().is_some()
+}
+
+fn start_demo(
+    mut idle: ResMut<MenuIdle>,
+    time: Res<Time>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    actions: Res<ButtonInput<Action>>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    if anything_pressed(&keys, &mouse, &actions) {
+        idle.0 = 0.;
+        return;
+    }
+    idle.0 += time.delta_secs();
+    if idle.0 >= DEMO_IDLE_SECONDS && !matches!(*game_state, NextState::Pending(_)) {
+        game_state.set(GameState::Demo);
+    }
+}
+
+fn leave_demo(
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    actions: Res<ButtonInput<Action>>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    if anything_pressed(&keys, &mouse, &actions) {
+        game_state.set(GameState::Menu);
+    }
+}
+
+// no death screen for the demo, straight back to the menu
+fn end_demo_on_death(mut game_state: ResMut<NextState<GameState>>) {
+    if matches!(*game_state, NextState::Pending(GameState::Dead)) {
+        game_state.set(GameState::Menu);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn view(tick: u32) -> AgentView {
+        AgentView { tick, scroll_speed: 300., ..default() }
+    }
+
+    fn ground(left: f32, width: f32, height: f32) -> SeenObstacle {
+        SeenObstacle { kind: ObstacleKind::Ground, left, right: left + width, bottom: 0., top: height }
+    }
+
+    #[test]
+    fn rule_agent_waits_for_the_last_moment_it_can_land_early() {
+        let tuning = Tuning::default();
+        let mut agent = RuleAgent;
+        let far = AgentView { obstacles: vec![ground(300., 30., 50.)], ..view(0) };
+        assert!(!agent.act(&far, &tuning).jump);
+        let close = AgentView { obstacles: vec![ground(60., 30., 50.)], ..view(0) };
+        assert!(agent.act(&close, &tuning).jump);
+        // nothing to do under a high flyer or in the air
+        let flyer = SeenObstacle { kind: ObstacleKind::Flying, bottom: 75., top: 99., ..ground(20., 40., 0.) };
+        assert!(!agent.act(&AgentView { obstacles: vec![flyer], ..view(0) }, &tuning).jump);
+        assert!(!agent.act(&AgentView { jumped: true, ..close }, &tuning).jump);
+    }
+
+    #[test]
+    fn scripted_agent_holds_until_the_next_step() {
+        let tuning = Tuning::default();
+        let mut agent = ScriptedAgent::new(vec![
+            (10, DinoInput { jump: true, jump_held: true, duck: false }),
+            (20, DinoInput { duck: true, ..default() }),
+        ]);
+        assert_eq!(agent.act(&view(5), &tuning), DinoInput::default());
+        assert!(agent.act(&view(10), &tuning).jump);
+        let held = agent.act(&view(15), &tuning);
+        assert!(!held.jump && held.jump_held);
+        assert!(agent.act(&view(25), &tuning).duck);
+    }
+}
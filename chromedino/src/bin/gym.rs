@@ -0,0 +1,31 @@
+// the gym over stdio for training scripts: one json request per line in, one json answer per line out
+// (see chromedino::gym::Request), an error comes back as {"error": "..."}
+// --mode and the other game arguments work here too
+
+use std::io::{self, BufRead, Write};
+
+use chromedino::difficulty::GameMode;
+use chromedino::gym::{DinoEnv, EnvConfig, Request};
+
+fn main() -> io::Result<()> {
+    let mut env = DinoEnv::new(EnvConfig {
+        mode: GameMode::from_args(),
+        ..Default::default()
+    });
+    let mut stdout = io::stdout().lock();
+    for line in io::stdin().lock().lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let answer = match serde_json::from_str::<Request>(&line) {
+            Ok(Request::Reset { seed }) => serde_json::to_string(&env.reset(seed)),
+            Ok(Request::Step { action }) => serde_json::to_string(&env.step(action)),
+            Ok(Request::Close) => break,
+            Err(e) => serde_json::to_string(&serde_json::json!({ "error": e.to_string() })),
+        };
+        writeln!(stdout, "{}", answer.map_err(io::Error::other)?)?;
+        stdout.flush()?;
+    }
+    Ok(())
+}
@@ -0,0 +1,259 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::f32::consts::{PI, TAU};
+
+use crate::actions::Action;
+use crate::collision::{self, Collision};
+use crate::difficulty::GameMode;
+use crate::settings::Settings;
+use crate::sound::Sound;
+use crate::tuning::Tuning;
+use crate::{Dino, GameplaySet, InRun, RunState, WorldCamera};
+
+// how much longer direction changes take with reduced motion
+const REDUCED_MOTION_TURN_SCALE: f32 = 2.;
+
+// the camera spins to a timeline from the tuning file: each step says how long it lasts,
+// how many turns it makes, on what curve, and when the spin changes direction
+pub struct ChoreographyPlugin;
+impl Plugin for ChoreographyPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CameraRotationController>()
+            .add_systems(OnEnter(InRun), reset_choreography)
+            .add_systems(FixedUpdate, follow_collisions.in_set(GameplaySet).after(collision::detect_collisions))
+            // on the tick, like the rest of the run, so the spin is the same at any frame rate
+            .add_systems(FixedUpdate, rotate_camera.in_set(GameplaySet).after(follow_collisions))
+            .add_systems(Update, debug_camera.run_if(in_state(RunState::Running)));
+    }
+}
+
+// when the direction of the spin flips
+#[derive(Serialize, Deserialize, PartialEq, Clone, Copy, Debug, Default)]
+pub enum CameraRotationPhase {
+    #[default]
+    Normal,
+    // at the end of the step
+    ChangeDirectionWhenTimer,
+    // every time the dino goes over an obstacle
+    ChangeDirectionWhenJump,
+    // every tick
+    Trippy,
+}
+
+// one stretch of the timeline; the last one repeats until the run ends
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+#[serde(default)]
+pub struct CameraStep {
+    pub seconds: f32,
+    pub phase: CameraRotationPhase,
+    // full turns over the step, starting where the last step stopped; 0 holds still
+    pub rotations: f32,
+    pub easing: EaseFunction,
+}
+impl Default for CameraStep {
+    fn default() -> Self {
+        CameraStep {
+            seconds: 20.,
+            phase: CameraRotationPhase::Normal,
+            rotations: 0.,
+            easing: EaseFunction::CubicInOut,
+        }
+    }
+}
+
+// a swing over to wherever the spin is now, instead of snapping when the direction flips
+struct Turn {
+    from: f32,
+    seconds: f32,
+}
+
+#[derive(Resource, Default)]
+pub(crate) struct CameraRotationController {
+    step: usize,
+    // into the current step
+    step_seconds: f32,
+    // the angle the current step started at
+    start_angle: f32,
+    // false for right, true for left
+    reversed: bool,
+    phase: CameraRotationPhase,
+    // over an obstacle right now, so one jump only flips once
+    over_obstacle: bool,
+    // what the camera is showing
+    angle: f32,
+    turn: Option<Turn>,
+    // picks the timeline
+    mode: GameMode,
+}
+impl CameraRotationController {
+    fn flip(&mut self) {
+        self.reversed = !self.reversed;
+        self.turn = Some(Turn { from: self.angle, seconds: 0. });
+    }
+
+    // the dino is above an obstacle
+    pub(crate) fn jumped_over_obstacle(&mut self) {
+        if self.phase == CameraRotationPhase::ChangeDirectionWhenJump && !self.over_obstacle {
+            self.flip();
+        }
+        self.over_obstacle = true;
+    }
+
+    pub(crate) fn landed(&mut self) {
+        self.over_obstacle = false;
+    }
+
+    // what the camera is showing, for the gym
+    pub(crate) fn angle(&self) -> f32 {
+        self.angle
+    }
+
+    pub(crate) fn phase(&self) -> CameraRotationPhase {
+        self.phase
+    }
+}
+
+fn reset_choreography(mut controller: ResMut<CameraRotationController>, mode: Res<GameMode>) {
+    *controller = CameraRotationController {
+        mode: *mode,
+        ..default()
+    };
+}
+
+fn ease(easing: EaseFunction, t: f32) -> f32 {
+    EasingCurve::new(0., 1., easing).sample_clamped(t)
+}
+
+// where a step has gotten to, t going from 0 to 1
+fn step_angle(step: &CameraStep, start_angle: f32, reversed: bool, t: f32) -> f32 {
+    let turned = step.rotations * TAU * ease(step.easing, t);
+    if reversed {
+        start_angle - turned
+    } else {
+        start_angle + turned
+    }
+}
+
+// the same angle as `angle`, on the side closest to `from`
+fn shortest_to(from: f32, angle: f32) -> f32 {
+    from + (angle - from + PI).rem_euclid(TAU) - PI
+}
+
+// debug: r skips to the next step, s flips the direction
+fn debug_camera(
+    mut controller: ResMut<CameraRotationController>,
+    tuning: Res<Tuning>,
+    actions: Res<ButtonInput<Action>>,
+) {
+    if actions.just_pressed(Action::DebugSkipCameraStep) {
+        let timeline = tuning.camera.timeline(controller.mode);
+        controller.step_seconds = timeline[controller.step.min(timeline.len() - 1)].seconds;
+    }
+    if actions.just_pressed(Action::DebugFlipCamera) {
+        controller.flip();
+    }
+}
+
+fn rotate_camera(
+    mut query: Query<&mut Transform, With<WorldCamera>>,
+    time: Res<Time>,
+    mut controller: ResMut<CameraRotationController>,
+    tuning: Res<Tuning>,
+    settings: Res<Settings>,
+    mut sounds: EventWriter<Sound>,
+) {
+    let timeline = tuning.camera.timeline(controller.mode);
+    let step_at = |index: usize| &timeline[index.min(timeline.len() - 1)];
+
+    controller.step_seconds += time.delta_secs();
+    let mut step = step_at(controller.step);
+    while controller.step_seconds >= step.seconds {
+        let end = step_angle(step, controller.start_angle, controller.reversed, 1.);
+        controller.start_angle = end.rem_euclid(TAU);
+        if step.phase == CameraRotationPhase::ChangeDirectionWhenTimer {
+            controller.flip();
+        }
+        controller.step_seconds -= step.seconds;
+        controller.step += 1;
+        let previous = step;
+        step = step_at(controller.step);
+        if previous.rotations == 0. && step.rotations != 0. && settings.file.camera_rotation {
+            sounds.send(Sound::CameraRotation);
+        }
+    }
+    controller.phase = step.phase;
+
+    // with reduced motion trippy spins like normal
+    if step.phase == CameraRotationPhase::Trippy && !settings.file.reduced_motion {
+        controller.reversed = !controller.reversed;
+    }
+    let t = controller.step_seconds / step.seconds;
+    let target = step_angle(step, controller.start_angle, controller.reversed, t);
+
+    // and direction changes swing over slower
+    let turn_seconds = if settings.file.reduced_motion {
+        tuning.camera.turn_seconds * REDUCED_MOTION_TURN_SCALE
+    } else {
+        tuning.camera.turn_seconds
+    };
+    let mut angle = match &mut controller.turn {
+        Some(turn) if turn.seconds < turn_seconds => {
+            turn.seconds += time.delta_secs();
+            let progress = ease(EaseFunction::CubicInOut, turn.seconds / turn_seconds);
+            turn.from.lerp(shortest_to(turn.from, target), progress)
+        }
+        _ => target,
+    };
+    if controller.turn.as_ref().is_some_and(|turn| turn.seconds >= turn_seconds) {
+        controller.turn = None;
+    }
+    // the timeline keeps going with the rotation turned off, the camera just doesn't follow it
+    if !settings.file.camera_rotation {
+        angle = 0.;
+    }
+    controller.angle = angle;
+
+    for mut transform in &mut query {
+        transform.rotation = Quat::from_rotation_z(angle);
+    }
+}
+
+// the jump phase flips the spin on every obstacle the dino gets over
+fn follow_collisions(
+    mut collisions: EventReader<Collision>,
+    dino_query: Query<&Dino>,
+    mut controller: ResMut<CameraRotationController>,
+) {
+    if dino_query.get_single().is_ok_and(|dino| !dino.jumped) {
+        controller.landed();
+    }
+    for collision in collisions.read() {
+        if let Collision::Cleared { .. } = collision {
+            controller.jumped_over_obstacle();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn steps_pick_up_where_the_last_one_stopped() {
+        let step = CameraStep {
+            rotations: 1.25,
+            easing: EaseFunction::Linear,
+            ..default()
+        };
+        assert_eq!(step_angle(&step, 0., false, 0.), 0.);
+        assert!((step_angle(&step, 0., false, 1.).rem_euclid(TAU) - TAU / 4.).abs() < 1e-4);
+        assert!((step_angle(&step, TAU / 4., true, 0.5) - (TAU / 4. - 0.625 * TAU)).abs() < 1e-4);
+    }
+
+    #[test]
+    fn turns_take_the_short_way() {
+        assert!((shortest_to(0., 3. * TAU + 0.1) - 0.1).abs() < 1e-4);
+        assert!((shortest_to(0.1, -0.1) + 0.1).abs() < 1e-4);
+        assert!((shortest_to(3. * PI / 4., -3. * PI / 4.) - 5. * PI / 4.).abs() < 1e-4);
+    }
+}
@@ -0,0 +1,261 @@
+use bevy::math::bounding::{Aabb2d, IntersectsVolume};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::tuning::Tuning;
+use crate::{Dino, Obstacle, Position};
+
+// ternary search steps for the distances that have no closed form here, plenty for pixels
+const SEARCH_STEPS: u32 = 40;
+
+// the dino against the obstacles, once a tick after it moved; what comes of it is up to whoever reads the events
+// (lib.rs dies, choreography.rs flips the spin, score.rs counts)
+pub struct CollisionPlugin;
+impl Plugin for CollisionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<Collision>();
+    }
+}
+
+#[derive(Event, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Collision {
+    // the dino ran into it
+    Hit { obstacle: Entity },
+    // the dino is above it for the first time
+    Cleared { obstacle: Entity },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct CollisionTuning {
+    // pixels the dino's hitbox is shrunk by on every side when it's checked against the obstacles
+    pub forgiveness: f32,
+}
+impl Default for CollisionTuning {
+    fn default() -> Self {
+        CollisionTuning { forgiveness: 0. }
+    }
+}
+
+// relative to the entity's Position
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Shape {
+    Aabb { center: Vec2, half_size: Vec2 },
+    Circle { center: Vec2, radius: f32 },
+    // everything within radius of the line from a to b
+    Capsule { a: Vec2, b: Vec2, radius: f32 },
+}
+impl Shape {
+    fn moved(self, by: Vec2) -> Shape {
+        match self {
+            Shape::Aabb { center, half_size } => Shape::Aabb { center: center + by, half_size },
+            Shape::Circle { center, radius } => Shape::Circle { center: center + by, radius },
+            Shape::Capsule { a, b, radius } => Shape::Capsule { a: a + by, b: b + by, radius },
+        }
+    }
+
+    fn inset(self, by: f32) -> Shape {
+        match self {
+            Shape::Aabb { center, half_size } => Shape::Aabb {
+                center,
+                half_size: (half_size - by).max(Vec2::ZERO),
+            },
+            Shape::Circle { center, radius } => Shape::Circle {
+                center,
+                radius: (radius - by).max(0.),
+            },
+            Shape::Capsule { a, b, radius } => Shape::Capsule {
+                a,
+                b,
+                radius: (radius - by).max(0.),
+            },
+        }
+    }
+
+    pub fn bounds(self) -> Rect {
+        match self {
+            Shape::Aabb { center, half_size } => Rect::from_center_half_size(center, half_size),
+            Shape::Circle { center, radius } => Rect::from_center_half_size(center, Vec2::splat(radius)),
+            Shape::Capsule { a, b, radius } => Rect::from_corners(a.min(b) - radius, a.max(b) + radius),
+        }
+    }
+
+    // a circle is a capsule that doesn't go anywhere
+    fn as_capsule(self) -> Option<(Vec2, Vec2, f32)> {
+        match self {
+            Shape::Aabb { .. } => None,
+            Shape::Circle { center, radius } => Some((center, center, radius)),
+            Shape::Capsule { a, b, radius } => Some((a, b, radius)),
+        }
+    }
+
+    // touching counts
+    pub fn intersects(self, other: Shape) -> bool {
+        match (self, other) {
+            (Shape::Aabb { center, half_size }, Shape::Aabb { center: other_center, half_size: other_half_size }) => {
+                Aabb2d::new(center, half_size).intersects(&Aabb2d::new(other_center, other_half_size))
+            }
+            (Shape::Aabb { center, half_size }, capsule) | (capsule, Shape::Aabb { center, half_size }) => {
+                let (a, b, radius) = capsule.as_capsule().unwrap();
+                let rect = Rect::from_center_half_size(center, half_size);
+                closest_along(a, b, |point| point.distance(point.clamp(rect.min, rect.max))) <= radius
+            }
+            _ => {
+                let (a, b, radius) = self.as_capsule().unwrap();
+                let (other_a, other_b, other_radius) = other.as_capsule().unwrap();
+                closest_along(a, b, |point| segment_distance(other_a, other_b, point)) <= radius + other_radius
+            }
+        }
+    }
+}
+
+fn segment_distance(a: Vec2, b: Vec2, point: Vec2) -> f32 {
+    let ab = b - a;
+    let t = if ab == Vec2::ZERO { 0. } else { ((point - a).dot(ab) / ab.length_squared()).clamp(0., 1.) };
+    point.distance(a + ab * t)
+}
+
+// the smallest `distance` along the line from a to b; the distance to something convex only has one dip
+fn closest_along(a: Vec2, b: Vec2, distance: impl Fn(Vec2) -> f32) -> f32 {
+    let (mut low, mut high) = (0f32, 1f32);
+    for _ in 0..SEARCH_STEPS {
+        let (left, right) = (low + (high - low) / 3., high - (high - low) / 3.);
+        if distance(a.lerp(b, left)) <= distance(a.lerp(b, right)) {
+            high = right;
+        } else {
+            low = left;
+        }
+    }
+    distance(a.lerp(b, (low + high) / 2.)).min(distance(a)).min(distance(b))
+}
+
+// what an entity collides with, any number of shapes; the sprite is drawn separately and can be bigger or smaller
+#[derive(Component, Clone, PartialEq, Debug)]
+pub struct Hitbox {
+    pub shapes: Vec<Shape>,
+}
+impl Hitbox {
+    // one box of that size, centered
+    pub fn rect(size: Vec2) -> Hitbox {
+        Hitbox {
+            shapes: vec![Shape::Aabb {
+                center: Vec2::ZERO,
+                half_size: size / 2.,
+            }],
+        }
+    }
+
+    // the rounded shape that fits in a box of that size, along its longer side
+    pub fn capsule(size: Vec2) -> Hitbox {
+        let radius = size.min_element() / 2.;
+        let reach = size / 2. - radius;
+        Hitbox {
+            shapes: vec![Shape::Capsule {
+                a: -reach,
+                b: reach,
+                radius,
+            }],
+        }
+    }
+
+    // around every shape, relative to the Position
+    pub fn bounds(&self) -> Rect {
+        self.shapes
+            .iter()
+            .map(|shape| shape.bounds())
+            .reduce(|bounds, next| bounds.union(next))
+            .unwrap_or_default()
+    }
+
+    pub fn size(&self) -> Vec2 {
+        self.bounds().size()
+    }
+
+    pub fn inset(&self, by: f32) -> Hitbox {
+        Hitbox {
+            shapes: self.shapes.iter().map(|shape| shape.inset(by)).collect(),
+        }
+    }
+
+    pub fn intersects(&self, position: Vec2, other: &Hitbox, other_position: Vec2) -> bool {
+        self.shapes.iter().any(|shape| {
+            other
+                .shapes
+                .iter()
+                .any(|other_shape| shape.moved(position).intersects(other_shape.moved(other_position)))
+        })
+    }
+}
+
+// an obstacle the dino already got over, so it's only cleared once
+#[derive(Component)]
+pub(crate) struct Cleared;
+
+pub(crate) fn detect_collisions(
+    mut commands: Commands,
+    dino_query: Query<(&Position, &Hitbox), With<Dino>>,
+    obstacle_query: Query<(Entity, &Position, &Hitbox, Has<Cleared>), With<Obstacle>>,
+    tuning: Res<Tuning>,
+    mut collisions: EventWriter<Collision>,
+) {
+    let Ok((dino_position, dino_hitbox)) = dino_query.get_single() else { return };
+    let dino_hitbox = dino_hitbox.inset(tuning.collision.forgiveness);
+    let dino_bounds = dino_hitbox.bounds();
+    let (dino_left, dino_right) = (dino_position.x + dino_bounds.min.x, dino_position.x + dino_bounds.max.x);
+    let dino_bottom = dino_position.y + dino_bounds.min.y;
+
+    for (obstacle, position, hitbox, cleared) in &obstacle_query {
+        if dino_hitbox.intersects(**dino_position, hitbox, **position) {
+            collisions.send(Collision::Hit { obstacle });
+            return;
+        }
+        let bounds = hitbox.bounds();
+        let under = dino_left <= position.x + bounds.max.x && dino_right >= position.x + bounds.min.x;
+        if !cleared && under && dino_bottom > position.y + bounds.max.y {
+            collisions.send(Collision::Cleared { obstacle });
+            commands.entity(obstacle).insert(Cleared);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shapes_touch() {
+        let rect = Hitbox::rect(Vec2::new(20., 60.));
+        assert!(rect.intersects(Vec2::ZERO, &rect, Vec2::new(20., 0.)));
+        assert!(!rect.intersects(Vec2::ZERO, &rect, Vec2::new(20.1, 0.)));
+        // the forgiveness takes it off both
+        assert!(!rect.inset(1.).intersects(Vec2::ZERO, &rect.inset(1.), Vec2::new(19., 0.)));
+
+        // a box's corner is further away than its side
+        let circle = Hitbox {
+            shapes: vec![Shape::Circle { center: Vec2::ZERO, radius: 10. }],
+        };
+        assert!(circle.intersects(Vec2::new(19., 0.), &rect, Vec2::ZERO));
+        assert!(!circle.intersects(Vec2::new(18., 37.), &rect, Vec2::ZERO));
+        assert!(circle.intersects(Vec2::new(16., 36.), &rect, Vec2::ZERO));
+
+        // side by side and end to end
+        let capsule = Hitbox::capsule(Vec2::new(40., 20.));
+        assert_eq!(capsule.size(), Vec2::new(40., 20.));
+        assert!(capsule.intersects(Vec2::ZERO, &capsule, Vec2::new(0., 19.9)));
+        assert!(!capsule.intersects(Vec2::ZERO, &capsule, Vec2::new(40.1, 0.)));
+        assert!(capsule.intersects(Vec2::ZERO, &circle, Vec2::new(29., 0.)));
+        // the corner of the box it fits in is empty
+        assert!(!capsule.intersects(Vec2::ZERO, &rect, Vec2::new(-28., 38.)));
+    }
+
+    #[test]
+    fn bounds_cover_every_shape() {
+        let hitbox = Hitbox {
+            shapes: vec![
+                Shape::Aabb { center: Vec2::new(0., 10.), half_size: Vec2::splat(5.) },
+                Shape::Circle { center: Vec2::new(10., 0.), radius: 2. },
+            ],
+        };
+        assert_eq!(hitbox.bounds(), Rect::new(-5., -2., 12., 15.));
+    }
+}
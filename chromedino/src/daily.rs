@@ -0,0 +1,174 @@
+use bevy::prelude::*;
+use chrono::{Datelike, Days, Local, NaiveDate};
+
+use crate::difficulty::GameMode;
+use crate::rng::{self, GameRng};
+use crate::score::{RunScore, SaveData};
+use crate::settings::Settings;
+use crate::{spawn_button, ButtonSize, Despawn, GameState, InRun};
+
+// how many weeks the calendar on the menu goes back, this one included
+const CALENDAR_WEEKS: u64 = 5;
+
+// one course a day: the seed comes from the local date and the daily mode has its own difficulty curves and camera
+// timeline in the tuning file, so everyone playing that day runs into the same things. the best of each day is kept
+// in the save, apart from the normal runs
+pub struct DailyPlugin;
+impl Plugin for DailyPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::Menu), (leave_daily, setup_daily).chain())
+            .add_systems(Update, daily_button.run_if(in_state(GameState::Menu)))
+            // before the seed is rolled, for play again too
+            .add_systems(OnEnter(InRun), seed_daily.before(rng::reseed))
+            .add_systems(
+                OnTransition {
+                    exited: GameState::Game,
+                    entered: GameState::Dead,
+                },
+                record_daily.run_if(resource_equals(GameMode::Daily)),
+            );
+    }
+}
+
+// the mode comes back when the player is back on the menu
+#[derive(Resource)]
+pub(crate) struct ModeBeforeDaily(GameMode);
+
+#[derive(Component)]
+struct DailyButton;
+
+pub fn today() -> NaiveDate {
+    Local::now().date_naive()
+}
+
+// the date written out, 20250301; readable on the death screen
+pub fn seed_for(date: NaiveDate) -> u64 {
+    date.year() as u64 * 10000 + date.month() as u64 * 100 + date.day() as u64
+}
+
+// whole weeks from monday, up to the one `today` is in
+fn calendar(today: NaiveDate) -> impl Iterator<Item = NaiveDate> {
+    let monday = today - Days::new(today.weekday().num_days_from_monday() as u64 + (CALENDAR_WEEKS - 1) * 7);
+    monday.iter_days().take(CALENDAR_WEEKS as usize * 7)
+}
+
+pub(crate) fn leave_daily(
+    mut commands: Commands,
+    mode_before: Option<Res<ModeBeforeDaily>>,
+    mut mode: ResMut<GameMode>,
+) {
+    if let Some(mode_before) = mode_before {
+        *mode = mode_before.0;
+        commands.remove_resource::<ModeBeforeDaily>();
+    }
+}
+
+fn setup_daily(mut commands: Commands, save: Res<SaveData>, settings: Res<Settings>) {
+    let palette = settings.file.palette();
+    let today = today();
+    let played = save.file.daily.get(&today);
+    commands
+    .spawn((Node {
+        position_type: PositionType::Absolute,
+        top: Val::Px(40.0),
+        left: Val::Px(30.0),
+        flex_direction: FlexDirection::Column,
+        align_items: AlignItems::Center,
+        row_gap: Val::Px(10.0),
+        ..default()
+    }, Despawn))
+    .with_children(|parent| {
+        spawn_button(parent, ButtonSize::Large, Val::Auto, Text::new("Daily Challenge"), DailyButton);
+        parent.spawn((
+            Text::new(match played {
+                Some(day) => format!("Today: {} ({} tries)", day.score, day.attempts),
+                None => "Today: not played".to_string(),
+            }),
+            TextFont::from_font_size(14.),
+            TextColor(palette.dim_text),
+        ));
+        // the day of the month and the best score of that day, if there is one
+        parent.spawn(Node {
+            display: Display::Grid,
+            grid_template_columns: RepeatedGridTrack::px(7, 40.),
+            column_gap: Val::Px(2.0),
+            row_gap: Val::Px(2.0),
+            ..default()
+        })
+        .with_children(|parent| {
+            for name in ["M", "T", "W", "T", "F", "S", "S"] {
+                parent.spawn((
+                    Text::new(name),
+                    TextFont::from_font_size(12.),
+                    TextColor(palette.dim_text),
+                ));
+            }
+            for date in calendar(today) {
+                let (text, color) = match save.file.daily.get(&date) {
+                    Some(day) => (format!("{}\n{}", date.day(), day.score), Color::srgb(0.9, 0.9, 0.9)),
+                    None if date > today => (String::new(), palette.dim_text),
+                    None => (format!("{}\n-", date.day()), palette.dim_text),
+                };
+                parent.spawn((Text::new(text), TextFont::from_font_size(12.), TextColor(color)));
+            }
+        });
+    });
+}
+
+fn daily_button(
+    mut commands: Commands,
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<DailyButton>)>,
+    mut mode: ResMut<GameMode>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    if !interaction_query.iter().any(|interaction| *interaction == Interaction::Pressed) {
+        return;
+    }
+    commands.insert_resource(ModeBeforeDaily(*mode));
+    *mode = GameMode::Daily;
+    game_state.set(GameState::Game);
+}
+
+// replays and the demo keep their own seed, but show the day's best like the run did
+fn seed_daily(
+    mode: Res<GameMode>,
+    game_state: Res<State<GameState>>,
+    mut game_rng: ResMut<GameRng>,
+    mut save: ResMut<SaveData>,
+) {
+    save.daily = (*mode == GameMode::Daily).then(today);
+    if let (Some(date), GameState::Game) = (save.daily, game_state.get()) {
+        game_rng.next_seed = Some(seed_for(date));
+    }
+}
+
+// for the day the run started on, even if it ended past midnight
+fn record_daily(run_score: Res<RunScore>, mut save: ResMut<SaveData>) {
+    let Some(date) = save.daily else { return };
+    save.last_run_was_best = save.file.record_daily(date, run_score.score(), run_score.seconds);
+    save.write();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_day_has_its_own_seed() {
+        let day = NaiveDate::from_ymd_opt(2025, 3, 1).unwrap();
+        assert_eq!(seed_for(day), 20250301);
+        assert_ne!(seed_for(day), seed_for(day.succ_opt().unwrap()));
+    }
+
+    #[test]
+    fn calendar_ends_on_the_week_of_today() {
+        // a wednesday
+        let today = NaiveDate::from_ymd_opt(2025, 3, 5).unwrap();
+        let days: Vec<_> = calendar(today).collect();
+        assert_eq!(days.len(), CALENDAR_WEEKS as usize * 7);
+        assert_eq!(days[0].weekday(), chrono::Weekday::Mon);
+        assert_eq!(days.last().unwrap().weekday(), chrono::Weekday::Sun);
+        assert!(days.contains(&today));
+        assert!(days.last().unwrap() < &(today + Days::new(7)));
+    }
+}
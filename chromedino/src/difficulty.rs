@@ -0,0 +1,287 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+const MODE_ARG: &str = "--mode";
+
+// picks the game mode (--mode relaxed); the curves for each mode are in the tuning file
+pub struct DifficultyPlugin;
+impl Plugin for DifficultyPlugin {
+    fn build(&self, app: &mut App) {
+        let mode = GameMode::from_args();
+        if mode != GameMode::default() {
+            info!("playing {}", mode.name());
+        }
+        app.insert_resource(mode);
+    }
+}
+
+#[derive(Resource, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum GameMode {
+    #[default]
+    Classic,
+    Relaxed,
+    Hardcore,
+    // the same course for everyone on the same day, see daily.rs
+    Daily,
+}
+impl GameMode {
+    pub const ALL: [GameMode; 4] = [GameMode::Classic, GameMode::Relaxed, GameMode::Hardcore, GameMode::Daily];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            GameMode::Classic => "classic",
+            GameMode::Relaxed => "relaxed",
+            GameMode::Hardcore => "hardcore",
+            GameMode::Daily => "daily",
+        }
+    }
+
+    // --mode, classic without it
+    pub fn from_args() -> GameMode {
+        mode_from_args().unwrap_or_default()
+    }
+
+    // what replays store
+    pub fn to_byte(self) -> u8 {
+        self as u8
+    }
+
+    pub fn from_byte(byte: u8) -> Option<GameMode> {
+        GameMode::ALL.get(byte as usize).copied()
+    }
+}
+
+// maps how far into the run it is (0 at the start, 1 at full speed) to how hard it is, also 0 to 1
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub enum DifficultyCurve {
+    Linear,
+    Quadratic,
+    SmoothStep,
+    // goes up in this many even jumps
+    Stepped(u32),
+    // (progress, difficulty) points with straight lines between them, flat before the first and after the last
+    Keyframes(Vec<(f32, f32)>),
+}
+impl DifficultyCurve {
+    pub fn sample(&self, progress: f32) -> f32 {
+        let t = progress.clamp(0., 1.);
+        let difficulty = match self {
+            DifficultyCurve::Linear => t,
+            DifficultyCurve::Quadratic => t * t,
+            DifficultyCurve::SmoothStep => t * t * (3. - 2. * t),
+            DifficultyCurve::Stepped(steps) => (t * *steps as f32).floor() / *steps as f32,
+            DifficultyCurve::Keyframes(keys) => keyframes(keys, t),
+        };
+        difficulty.clamp(0., 1.)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            DifficultyCurve::Stepped(0) => Err("Stepped needs at least 1 step".to_string()),
+            DifficultyCurve::Keyframes(keys) if keys.is_empty() => Err("Keyframes needs at least 1 key".to_string()),
+            DifficultyCurve::Keyframes(keys) if keys.windows(2).any(|pair| pair[0].0 >= pair[1].0) => {
+                Err("Keyframes have to be in order of progress".to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+fn keyframes(keys: &[(f32, f32)], t: f32) -> f32 {
+    let next = keys.partition_point(|&(at, _)| at <= t);
+    match (next.checked_sub(1).map(|i| keys[i]), keys.get(next)) {
+        (Some((from_at, from)), Some(&(to_at, to))) => from + (to - from) * (t - from_at) / (to_at - from_at),
+        (Some((_, value)), None) | (None, Some(&(_, value))) => value,
+        (None, None) => 0.,
+    }
+}
+
+// how hard each part of the game is right now, 0 to 1
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct Difficulty {
+    pub speed: f32,
+    pub spacing: f32,
+    pub size: f32,
+    pub flyers: f32,
+    pub patterns: f32,
+    pub power_ups: f32,
+}
+
+// each part of the game ramps up on its own curve
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(default)]
+pub struct DifficultyCurves {
+    // between the min and max scroll speed
+    pub speed: DifficultyCurve,
+    // how much of the random spacing is taken away
+    pub spacing: DifficultyCurve,
+    // how much of the obstacle size range is used, from the smallest up
+    pub size: DifficultyCurve,
+    // how close the flyer chance is to its max
+    pub flyers: DifficultyCurve,
+    // how close the chance of a cluster instead of a single obstacle is to its max
+    pub patterns: DifficultyCurve,
+    // how close the chance of a pickup after a pattern is to its max
+    pub power_ups: DifficultyCurve,
+}
+impl Default for DifficultyCurves {
+    fn default() -> Self {
+        DifficultyCurves {
+            speed: DifficultyCurve::Quadratic,
+            spacing: DifficultyCurve::Linear,
+            size: DifficultyCurve::Keyframes(vec![(0., 1.)]),
+            flyers: DifficultyCurve::Keyframes(vec![(0.25, 0.), (1., 1.)]),
+            patterns: DifficultyCurve::Keyframes(vec![(0.1, 0.), (1., 1.)]),
+            power_ups: DifficultyCurve::Linear,
+        }
+    }
+}
+impl DifficultyCurves {
+    pub fn sample(&self, progress: f32) -> Difficulty {
+        Difficulty {
+            speed: self.speed.sample(progress),
+            spacing: self.spacing.sample(progress),
+            size: self.size.sample(progress),
+            flyers: self.flyers.sample(progress),
+            patterns: self.patterns.sample(progress),
+            power_ups: self.power_ups.sample(progress),
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        for (name, curve) in [
+            ("speed", &self.speed),
+            ("spacing", &self.spacing),
+            ("size", &self.size),
+            ("flyers", &self.flyers),
+            ("patterns", &self.patterns),
+            ("power_ups", &self.power_ups),
+        ] {
+            curve.validate().map_err(|e| format!("{name}: {e}"))?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(default)]
+pub struct DifficultyTuning {
+    pub classic: DifficultyCurves,
+    pub relaxed: DifficultyCurves,
+    pub hardcore: DifficultyCurves,
+    pub daily: DifficultyCurves,
+}
+impl Default for DifficultyTuning {
+    fn default() -> Self {
+        DifficultyTuning {
+            classic: DifficultyCurves::default(),
+            // slow to get going, and it never gets to full speed or the biggest obstacles
+            relaxed: DifficultyCurves {
+                speed: DifficultyCurve::Keyframes(vec![(0., 0.), (1., 0.6)]),
+                size: DifficultyCurve::Keyframes(vec![(0., 0.3), (1., 0.7)]),
+                flyers: DifficultyCurve::Keyframes(vec![(0.5, 0.), (1., 0.5)]),
+                patterns: DifficultyCurve::Keyframes(vec![(0.3, 0.), (1., 0.5)]),
+                power_ups: DifficultyCurve::Keyframes(vec![(0., 0.5), (1., 1.)]),
+                ..default()
+            },
+            // everything right away, then the speed comes in jumps, and no help
+            hardcore: DifficultyCurves {
+                speed: DifficultyCurve::Stepped(4),
+                spacing: DifficultyCurve::SmoothStep,
+                flyers: DifficultyCurve::Linear,
+                patterns: DifficultyCurve::Linear,
+                power_ups: DifficultyCurve::Keyframes(vec![(0., 0.)]),
+                ..default()
+            },
+            // its own, so changing the others doesn't change the daily course
+            daily: DifficultyCurves {
+                speed: DifficultyCurve::SmoothStep,
+                size: DifficultyCurve::Keyframes(vec![(0., 0.5), (1., 1.)]),
+                flyers: DifficultyCurve::Keyframes(vec![(0.2, 0.), (1., 1.)]),
+                power_ups: DifficultyCurve::Keyframes(vec![(0., 0.5)]),
+                ..default()
+            },
+        }
+    }
+}
+impl DifficultyTuning {
+    pub fn curves(&self, mode: GameMode) -> &DifficultyCurves {
+        match mode {
+            GameMode::Classic => &self.classic,
+            GameMode::Relaxed => &self.relaxed,
+            GameMode::Hardcore => &self.hardcore,
+            GameMode::Daily => &self.daily,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        for mode in GameMode::ALL {
+            self.curves(mode)
+                .validate()
+                .map_err(|e| format!("difficulty.{}.{e}", mode.name()))?;
+        }
+        Ok(())
+    }
+}
+
+fn mode_from_args() -> Option<GameMode> {
+    let value = crate::arg_value(MODE_ARG)?;
+    let mode = GameMode::ALL.into_iter().find(|mode| mode.name().eq_ignore_ascii_case(&value));
+    if mode.is_none() {
+        warn!("unknown mode {value:?}, playing {}", GameMode::default().name());
+    }
+    mode
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn samples(curve: &DifficultyCurve) -> Vec<f32> {
+        [0., 0.25, 0.5, 0.75, 1.].iter().map(|&t| curve.sample(t)).collect()
+    }
+
+    #[test]
+    fn simple_curves() {
+        assert_eq!(samples(&DifficultyCurve::Linear), [0., 0.25, 0.5, 0.75, 1.]);
+        assert_eq!(samples(&DifficultyCurve::Quadratic), [0., 0.0625, 0.25, 0.5625, 1.]);
+        assert_eq!(samples(&DifficultyCurve::SmoothStep), [0., 0.15625, 0.5, 0.84375, 1.]);
+    }
+
+    #[test]
+    fn stepped() {
+        let curve = DifficultyCurve::Stepped(2);
+        assert_eq!(samples(&curve), [0., 0., 0.5, 0.5, 1.]);
+        assert_eq!(curve.sample(0.49), 0.);
+    }
+
+    #[test]
+    fn keyframes() {
+        let curve = DifficultyCurve::Keyframes(vec![(0.25, 0.2), (0.5, 1.), (1., 0.5)]);
+        assert_eq!(samples(&curve), [0.2, 0.2, 1., 0.75, 0.5]);
+        assert_eq!(samples(&DifficultyCurve::Keyframes(vec![(0.5, 0.3)])), [0.3; 5]);
+    }
+
+    #[test]
+    fn out_of_range_is_clamped() {
+        assert_eq!(DifficultyCurve::Linear.sample(-1.), 0.);
+        assert_eq!(DifficultyCurve::Quadratic.sample(2.), 1.);
+        assert_eq!(DifficultyCurve::Keyframes(vec![(0., 3.)]).sample(0.5), 1.);
+    }
+
+    #[test]
+    fn rejects_bad_curves() {
+        assert!(DifficultyCurve::Stepped(0).validate().is_err());
+        assert!(DifficultyCurve::Keyframes(Vec::new()).validate().is_err());
+        assert!(DifficultyCurve::Keyframes(vec![(0.5, 0.), (0.5, 1.)]).validate().is_err());
+        assert!(DifficultyTuning::default().validate().is_ok());
+    }
+
+    #[test]
+    fn modes_round_trip_through_bytes() {
+        for mode in GameMode::ALL {
+            assert_eq!(GameMode::from_byte(mode.to_byte()), Some(mode));
+        }
+        assert_eq!(GameMode::from_byte(GameMode::ALL.len() as u8), None);
+    }
+}
@@ -0,0 +1,203 @@
+use bevy::prelude::*;
+
+use crate::collision::{self, Hitbox};
+use crate::daily;
+use crate::difficulty::GameMode;
+use crate::power_ups::PowerUps;
+use crate::replay::{self, Recording, Replay};
+use crate::rng::{self, GameRng};
+use crate::score::{self, RunScore, SaveData};
+use crate::settings::Settings;
+use crate::tuning::Tuning;
+use crate::{
+    spawn_button, ButtonSize, Despawn, Dino, GameManager, GameState, GameplaySet, InRun, Position,
+    PreviousPosition, RunTick, DINO_HEIGHT, DINO_WIDTH, PLAIN_HEIGHT,
+};
+
+// how see-through the ghost is, and how long it takes to fade out once it died
+const GHOST_ALPHA: f32 = 0.35;
+const GHOST_FADE_SECONDS: f32 = 0.5;
+
+// the best run, replayed next to the player when a run has its seed and mode: a dino of its own, driven by the
+// recorded inputs through the same physics and handed the power ups it picked up when it picked them up. it never
+// looks at the player's obstacles, which a run that went differently may have moved or broken: it dies where its
+// recording ends, so the player's run plays out the same with or without it
+pub struct GhostPlugin {
+    pub save_to_disk: bool,
+}
+impl Plugin for GhostPlugin {
+    fn build(&self, app: &mut App) {
+        let best = if self.save_to_disk { load_best() } else { None };
+        app.insert_resource(BestRun(best))
+            .add_systems(OnEnter(GameState::Menu), setup_race_button.after(daily::leave_daily))
+            .add_systems(Update, race_button.run_if(in_state(GameState::Menu)))
+            .add_systems(OnEnter(InRun), spawn_ghost.after(rng::reseed).run_if(in_state(GameState::Game)))
+            .add_systems(
+                FixedUpdate,
+                run_ghost
+                    .in_set(GameplaySet)
+                    .after(collision::detect_collisions)
+                    .before(crate::advance_tick),
+            )
+            .add_systems(Update, fade_ghost.run_if(in_state(InRun)))
+            .add_systems(
+                OnTransition {
+                    exited: GameState::Game,
+                    entered: GameState::Dead,
+                },
+                remember_best.after(replay::save_recording),
+            );
+    }
+}
+
+// what the ghost replays
+#[derive(Resource, Default)]
+pub struct BestRun(pub Option<Replay>);
+
+#[derive(Component)]
+pub struct Ghost {
+    pub dino: Dino,
+    pub replay: Replay,
+    // how far it got, like RunScore's; its x is this minus the player's
+    pub distance: f32,
+    pub dead: bool,
+    power_ups: PowerUps,
+    seconds_dead: f32,
+}
+impl Ghost {
+    // how far ahead of it the player is, in points
+    pub fn lead(&self, run_score: &RunScore) -> i32 {
+        ((run_score.distance - self.distance) * score::SCORE_PER_PIXEL).round() as i32
+    }
+}
+
+#[derive(Component)]
+struct RaceButton;
+
+fn load_best() -> Option<Replay> {
+    let path = score::data_path(replay::BEST_REPLAY_FILE)?;
+    if !path.exists() {
+        return None;
+    }
+    Replay::load(&path)
+        .inspect_err(|e| warn!("couldn't load the best replay {}: {e}", path.display()))
+        .ok()
+}
+
+fn remember_best(recording: Res<Recording>, save: Res<SaveData>, mut best: ResMut<BestRun>) {
+    if save.last_run_was_best && save.daily.is_none() {
+        best.0 = Some((**recording).clone());
+    }
+}
+
+// only with a best run in this mode to race
+fn setup_race_button(mut commands: Commands, best: Res<BestRun>, mode: Res<GameMode>) {
+    if !best.0.as_ref().is_some_and(|best| best.mode == *mode) {
+        return;
+    }
+    commands
+    .spawn((Node {
+        width: Val::Percent(100.0),
+        position_type: PositionType::Absolute,
+        bottom: Val::Px(110.0),
+        justify_content: JustifyContent::Center,
+        ..default()
+    }, Despawn))
+    .with_children(|parent| {
+        spawn_button(parent, ButtonSize::Large, Val::Auto, Text::new("Race your best"), RaceButton);
+    });
+}
+
+// the next run gets the best one's seed, so the ghost shows up
+fn race_button(
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<RaceButton>)>,
+    best: Res<BestRun>,
+    mut game_rng: ResMut<GameRng>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    if !interaction_query.iter().any(|interaction| *interaction == Interaction::Pressed) {
+        return;
+    }
+    let Some(best) = &best.0 else { return };
+    game_rng.next_seed = Some(best.seed);
+    game_state.set(GameState::Game);
+}
+
+fn spawn_ghost(
+    mut commands: Commands,
+    best: Res<BestRun>,
+    game_rng: Res<GameRng>,
+    mode: Res<GameMode>,
+    settings: Res<Settings>,
+) {
+    let Some(best) = &best.0 else { return };
+    if best.seed != game_rng.seed || best.mode != *mode {
+        return;
+    }
+    let position = Vec2::new(0., PLAIN_HEIGHT + DINO_HEIGHT / 2.);
+    commands.spawn((
+        Sprite::from_color(
+            settings.file.palette().dino.with_alpha(GHOST_ALPHA),
+            Vec2::new(DINO_WIDTH, DINO_HEIGHT),
+        ),
+        // behind the player
+        Transform::from_translation(position.extend(-1.)),
+        Ghost {
+            dino: Dino::default(),
+            replay: best.clone(),
+            distance: 0.,
+            dead: false,
+            power_ups: PowerUps::default(),
+            seconds_dead: 0.,
+        },
+        Hitbox::rect(Vec2::new(DINO_WIDTH, DINO_HEIGHT)),
+        Position(position),
+        PreviousPosition(position),
+        Despawn,
+    ));
+}
+
+// the same tick the player just had, with the recorded input and its own scroll
+fn run_ghost(
+    mut ghost_query: Query<(&mut Ghost, &mut Position, &mut Hitbox)>,
+    run_tick: Res<RunTick>,
+    run_score: Res<RunScore>,
+    game_manager: Res<GameManager>,
+    tuning: Res<Tuning>,
+    time: Res<Time>,
+) {
+    let delta = time.delta_secs();
+    for (mut ghost, mut position, mut hitbox) in &mut ghost_query {
+        if ghost.dead {
+            position.x = ghost.distance - run_score.distance;
+            continue;
+        }
+        let ghost = &mut *ghost;
+        let input = ghost.replay.input(**run_tick);
+        let size = ghost.dino.step(&mut position.0, &input, &tuning.jump, &ghost.power_ups, delta);
+        *hitbox = Hitbox::rect(size);
+        let game_speed = game_manager.difficulty.speed * ghost.power_ups.speed_scale(&tuning.power_ups);
+        ghost.distance += delta * tuning.speed.scroll_speed(game_speed);
+        position.x = ghost.distance - run_score.distance;
+
+        for (_, power_up) in ghost.replay.pickups.iter().filter(|(tick, _)| *tick == **run_tick) {
+            ghost.power_ups.grant(*power_up, power_up.seconds(&tuning.power_ups));
+        }
+        ghost.power_ups.tick(delta);
+        // its run ended here; it already hit whatever killed it, whatever this one did differently
+        if **run_tick + 1 >= ghost.replay.ticks {
+            ghost.dead = true;
+        }
+    }
+}
+
+fn fade_ghost(mut query: Query<(&mut Ghost, &mut Sprite)>, time: Res<Time>) {
+    for (mut ghost, mut sprite) in &mut query {
+        if !ghost.dead {
+            continue;
+        }
+        ghost.seconds_dead += time.delta_secs();
+        let alpha = GHOST_ALPHA * (1. - ghost.seconds_dead / GHOST_FADE_SECONDS).max(0.);
+        sprite.color.set_alpha(alpha);
+    }
+}
@@ -0,0 +1,260 @@
+use bevy::input::InputPlugin;
+use bevy::prelude::*;
+use bevy::state::app::StatesPlugin;
+use bevy::time::TimeUpdateStrategy;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::autoplay::{self, Agent, AgentView, Autopilot};
+use crate::choreography::{CameraRotationController, CameraRotationPhase};
+use crate::difficulty::GameMode;
+use crate::rng::GameRng;
+use crate::score::RunScore;
+use crate::tuning::Tuning;
+use crate::{DinoInput, GameConfig, GamePlugin, GameState, DINO_WIDTH, TICKS_PER_SECOND};
+
+// how many obstacles ahead an observation has, missing ones are NO_OBSTACLE
+pub const NEXT_OBSTACLES: usize = 3;
+const NO_OBSTACLE: ObstacleObservation = ObstacleObservation {
+    distance: 2000.,
+    width: 0.,
+    height: 0.,
+    bottom: 0.,
+};
+// on top of the points, for dying
+const DEATH_REWARD: f32 = -100.;
+
+// the game as a reinforcement learning environment, headless and one tick per update:
+//   let mut env = DinoEnv::new(EnvConfig::default());
+//   let mut observation = env.reset(seed);
+//   loop { let step = env.step(GymAction::Jump); if step.done { break } }
+// src/bin/gym.rs serves it as json lines over stdio
+pub struct DinoEnv {
+    app: App,
+    action: Arc<Mutex<GymAction>>,
+    config: EnvConfig,
+    observation: Observation,
+    score: u32,
+    done: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct EnvConfig {
+    // ticks (1/120s) every step plays the same action for
+    pub ticks_per_step: u32,
+    pub mode: GameMode,
+    // the camera timeline in here spins the camera the observation reports
+    pub tuning: Tuning,
+}
+impl Default for EnvConfig {
+    fn default() -> Self {
+        EnvConfig {
+            ticks_per_step: 1,
+            mode: GameMode::default(),
+            tuning: Tuning::default(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum GymAction {
+    #[default]
+    Run,
+    // press and hold; anything else lets go, which cuts a rising jump short
+    Jump,
+    // on the ground, a fast fall in the air
+    Duck,
+}
+impl GymAction {
+    pub const ALL: [GymAction; 3] = [GymAction::Run, GymAction::Jump, GymAction::Duck];
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub struct ObstacleObservation {
+    // from the front of the dino to the obstacle's left edge
+    pub distance: f32,
+    pub width: f32,
+    pub height: f32,
+    // above the ground, 0 unless it flies
+    pub bottom: f32,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Observation {
+    pub tick: u32,
+    // 0 to 1
+    pub game_speed: f32,
+    // of the dino's feet
+    pub height: f32,
+    pub velocity: f32,
+    pub ducking: bool,
+    // closest first
+    pub obstacles: [ObstacleObservation; NEXT_OBSTACLES],
+    pub camera_angle: f32,
+    pub camera_phase: CameraRotationPhase,
+}
+impl Observation {
+    fn new(view: &AgentView, camera: &CameraRotationController) -> Self {
+        let mut obstacles = [NO_OBSTACLE; NEXT_OBSTACLES];
+        for (slot, obstacle) in obstacles.iter_mut().zip(&view.obstacles) {
+            *slot = ObstacleObservation {
+                distance: obstacle.left - DINO_WIDTH / 2.,
+                width: obstacle.right - obstacle.left,
+                height: obstacle.top - obstacle.bottom,
+                bottom: obstacle.bottom,
+            };
+        }
+        Observation {
+            tick: view.tick,
+            game_speed: view.game_speed,
+            height: view.height,
+            velocity: view.velocity,
+            ducking: view.ducking,
+            obstacles,
+            camera_angle: camera.angle(),
+            camera_phase: camera.phase(),
+        }
+    }
+
+    // flat, for networks: the numbers above in order, ducking as 0 or 1, the phase as its index
+    pub fn to_vec(&self) -> Vec<f32> {
+        let mut values = vec![self.game_speed, self.height, self.velocity, self.ducking as u8 as f32];
+        for obstacle in &self.obstacles {
+            values.extend([obstacle.distance, obstacle.width, obstacle.height, obstacle.bottom]);
+        }
+        values.extend([self.camera_angle, self.camera_phase as u8 as f32]);
+        values
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Step {
+    pub observation: Observation,
+    // points scored during the step, DEATH_REWARD more on the step the dino dies
+    pub reward: f32,
+    pub done: bool,
+}
+
+// hands the env's action to the game where the keyboard would
+struct EnvAgent(Arc<Mutex<GymAction>>);
+impl Agent for EnvAgent {
+    fn name(&self) -> &str {
+        "gym"
+    }
+
+    fn act(&mut self, _view: &AgentView, _tuning: &Tuning) -> DinoInput {
+        let action = self.0.lock().map(|action| *action).unwrap_or_default();
+        DinoInput {
+            jump: action == GymAction::Jump,
+            jump_held: action == GymAction::Jump,
+            duck: action == GymAction::Duck,
+        }
+    }
+}
+
+impl DinoEnv {
+    pub fn new(config: EnvConfig) -> Self {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, StatesPlugin, InputPlugin))
+            .add_plugins(GamePlugin {
+                config: GameConfig {
+                    save_to_disk: false,
+                    ..default()
+                },
+            })
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(
+                1. / TICKS_PER_SECOND,
+            )))
+            .insert_resource(config.mode)
+            .insert_resource(config.tuning.clone());
+        let action = Arc::new(Mutex::new(GymAction::Run));
+        app.world_mut().resource_mut::<Autopilot>().agent = Some(Box::new(EnvAgent(action.clone())));
+        app.update();
+
+        DinoEnv {
+            app,
+            action,
+            config,
+            observation: Observation::new(&AgentView::default(), &CameraRotationController::default()),
+            score: 0,
+            done: true,
+        }
+    }
+
+    // starts a new run with this seed
+    pub fn reset(&mut self, seed: u64) -> Observation {
+        self.app.world_mut().resource_mut::<GameRng>().fixed_seed = Some(seed);
+        self.set_action(GymAction::Run);
+        // through the menu, so a run in progress is cleared like the pause menu's main menu button does
+        for state in [GameState::Menu, GameState::Game] {
+            self.app.world_mut().resource_mut::<NextState<GameState>>().set(state);
+            self.app.update();
+        }
+        self.score = 0;
+        self.done = false;
+        self.observe();
+        self.observation.clone()
+    }
+
+    pub fn step(&mut self, action: GymAction) -> Step {
+        if self.done {
+            return Step {
+                observation: self.observation.clone(),
+                reward: 0.,
+                done: true,
+            };
+        }
+        self.set_action(action);
+        for _ in 0..self.config.ticks_per_step.max(1) {
+            self.app.update();
+            if *self.app.world().resource::<State<GameState>>() != GameState::Game {
+                self.done = true;
+                break;
+            }
+        }
+        self.observe();
+
+        let score = self.app.world().resource::<RunScore>().score();
+        let mut reward = score.saturating_sub(self.score) as f32;
+        self.score = score;
+        if self.done {
+            reward += DEATH_REWARD;
+        }
+        Step {
+            observation: self.observation.clone(),
+            reward,
+            done: self.done,
+        }
+    }
+
+    // the app underneath, for anything the api doesn't cover
+    pub fn app_mut(&mut self) -> &mut App {
+        &mut self.app
+    }
+
+    fn set_action(&mut self, action: GymAction) {
+        if let Ok(mut current) = self.action.lock() {
+            *current = action;
+        }
+    }
+
+    // the dead dino is gone by the time the state changes, so the last observation stays
+    fn observe(&mut self) {
+        let world = self.app.world_mut();
+        if let Some(view) = autoplay::observe(world) {
+            self.observation = Observation::new(&view, world.resource::<CameraRotationController>());
+        }
+    }
+}
+
+// what the stdio protocol takes, one per line:
+//   {"reset": {"seed": 7}}  {"step": {"action": "jump"}}  "close"
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Request {
+    Reset { seed: u64 },
+    Step { action: GymAction },
+    Close,
+}
@@ -0,0 +1,176 @@
+use bevy::prelude::*;
+
+use crate::ghost::Ghost;
+use crate::power_ups::PowerUps;
+use crate::score::{RunScore, SaveData};
+use crate::settings::Settings;
+use crate::sound::Sound;
+use crate::{Despawn, GameManager, InRun};
+
+// like the chrome dino: the score blinks for a bit every 100 points
+const MILESTONE_POINTS: u32 = 100;
+const MILESTONE_FLASH_SECONDS: f32 = 1.;
+const MILESTONE_BLINK_SECONDS: f32 = 0.125;
+
+// score, best and speed on top of the run; ui is drawn by the ui camera, so the world camera spinning doesn't touch it
+pub struct HudPlugin;
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MilestoneFlash>()
+            .add_systems(OnEnter(InRun), setup_hud)
+            .add_systems(Update, (check_milestone, update_hud, update_power_ups, update_ghost_lead).chain().run_if(in_state(InRun)));
+    }
+}
+
+#[derive(Component)]
+struct HudScore;
+
+#[derive(Component)]
+struct HudSpeed;
+
+// the power ups that are on, and for how long
+#[derive(Component)]
+struct HudPowerUps;
+
+// how far ahead of the ghost the player is, when there's one
+#[derive(Component)]
+struct HudGhost;
+
+// the last milestone reached, and how long it's still shown for
+#[derive(Resource, Default)]
+struct MilestoneFlash {
+    milestone: u32,
+    seconds_left: f32,
+}
+
+// the milestone the score just got to, if it passed one since `previous`
+fn crossed_milestone(previous: u32, score: u32) -> Option<u32> {
+    let milestone = score / MILESTONE_POINTS * MILESTONE_POINTS;
+    (milestone > previous && milestone > 0).then_some(milestone)
+}
+
+fn setup_hud(mut commands: Commands, mut flash: ResMut<MilestoneFlash>, settings: Res<Settings>) {
+    *flash = MilestoneFlash::default();
+    commands
+    .spawn((Node {
+        position_type: PositionType::Absolute,
+        top: Val::Px(20.0),
+        right: Val::Px(30.0),
+        flex_direction: FlexDirection::Column,
+        align_items: AlignItems::FlexEnd,
+        ..default()
+    }, Despawn))
+    .with_children(|parent| {
+        parent.spawn((
+            Text::default(),
+            TextColor(Color::srgb(0.9, 0.9, 0.9)),
+            HudScore,
+        ));
+        parent.spawn((
+            Text::default(),
+            TextFont::from_font_size(14.),
+            TextColor(settings.file.palette().dim_text),
+            HudSpeed,
+        ));
+        parent.spawn((
+            Text::default(),
+            TextFont::from_font_size(14.),
+            TextColor(Color::srgb(0.9, 0.9, 0.9)),
+            HudPowerUps,
+        ));
+        parent.spawn((
+            Text::default(),
+            TextFont::from_font_size(14.),
+            TextColor(settings.file.palette().dim_text),
+            HudGhost,
+        ));
+    });
+}
+
+fn check_milestone(
+    run_score: Res<RunScore>,
+    mut flash: ResMut<MilestoneFlash>,
+    time: Res<Time>,
+    mut sounds: EventWriter<Sound>,
+) {
+    flash.seconds_left = (flash.seconds_left - time.delta_secs()).max(0.);
+    if let Some(milestone) = crossed_milestone(flash.milestone, run_score.score()) {
+        flash.milestone = milestone;
+        flash.seconds_left = MILESTONE_FLASH_SECONDS;
+        sounds.send(Sound::Milestone);
+    }
+}
+
+fn update_hud(
+    run_score: Res<RunScore>,
+    save: Res<SaveData>,
+    game_manager: Res<GameManager>,
+    flash: Res<MilestoneFlash>,
+    settings: Res<Settings>,
+    mut score_query: Query<(&mut Text, &mut Visibility), With<HudScore>>,
+    mut speed_query: Query<&mut Text, (With<HudSpeed>, Without<HudScore>)>,
+) {
+    let score = run_score.score();
+    let best = save.best().max(score);
+    for (mut text, mut visibility) in &mut score_query {
+        if flash.seconds_left > 0. {
+            // the milestone itself blinks instead of the running score, or just stays there with reduced motion
+            let blink = ((MILESTONE_FLASH_SECONDS - flash.seconds_left) / MILESTONE_BLINK_SECONDS) as u32;
+            text.0 = format!("HI {best:05}  {:05}", flash.milestone);
+            *visibility = if blink % 2 == 1 || settings.file.reduced_motion {
+                Visibility::Inherited
+            } else {
+                Visibility::Hidden
+            };
+        } else {
+            text.0 = format!("HI {best:05}  {score:05}");
+            *visibility = Visibility::Inherited;
+        }
+    }
+
+    let seconds = run_score.seconds as u32;
+    for mut text in &mut speed_query {
+        text.0 = format!(
+            "speed {:.0}%  {}:{:02}  cleared {}",
+            game_manager.game_speed * 100.,
+            seconds / 60,
+            seconds % 60,
+            run_score.cleared
+        );
+    }
+}
+
+fn update_power_ups(power_ups: Res<PowerUps>, mut query: Query<&mut Text, With<HudPowerUps>>) {
+    let active: Vec<String> = power_ups
+        .active()
+        .map(|(power_up, seconds)| format!("{} {}s", power_up.name(), seconds.ceil()))
+        .collect();
+    for mut text in &mut query {
+        text.0 = active.join("  ");
+    }
+}
+
+fn update_ghost_lead(
+    ghost_query: Query<&Ghost>,
+    run_score: Res<RunScore>,
+    mut query: Query<&mut Text, With<HudGhost>>,
+) {
+    let lead = ghost_query.get_single().ok().map(|ghost| format!("vs ghost {:+}", ghost.lead(&run_score)));
+    for mut text in &mut query {
+        text.0 = lead.clone().unwrap_or_default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn milestones_every_hundred() {
+        assert_eq!(crossed_milestone(0, 99), None);
+        assert_eq!(crossed_milestone(0, 100), Some(100));
+        assert_eq!(crossed_milestone(100, 150), None);
+        // a big jump only flashes the latest one
+        assert_eq!(crossed_milestone(100, 320), Some(300));
+    }
+}
@@ -0,0 +1,423 @@
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::prelude::*;
+use chrono::{DateTime, Local};
+
+use crate::actions::{self, Action};
+use crate::score::{RunRecord, SaveData};
+use crate::settings::Settings;
+use crate::{despawn_screen, spawn_button, ButtonSize, Despawn, GameState};
+
+const NAME_LENGTH_MAX: usize = 12;
+
+// the top runs of every local profile: a run that makes it asks for a name on the death screen, the menu switches
+// between the profiles (and makes new ones), and the leaderboard screen lists them
+pub struct LeaderboardPlugin;
+impl Plugin for LeaderboardPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LeaderboardSort>()
+            // typing a name isn't jumping, confirming or muting
+            .add_systems(
+                PreUpdate,
+                hold_actions.after(actions::update_actions).run_if(resource_exists::<NameEntry>),
+            )
+            .add_systems(OnEnter(GameState::Dead), offer_name_entry)
+            .add_systems(OnEnter(GameState::Menu), setup_profiles)
+            .add_systems(Update, (profile_buttons, update_profiles).chain().run_if(in_state(GameState::Menu)))
+            .add_systems(Update, (type_name, update_name_entry).chain().distributive_run_if(resource_exists::<NameEntry>))
+            .add_systems(OnExit(GameState::Dead), close_name_entry)
+            .add_systems(OnExit(GameState::Menu), close_name_entry)
+            // the leaderboard screen
+            .add_systems(OnEnter(GameState::Leaderboard), setup_leaderboard)
+            .add_systems(
+                Update,
+                (leaderboard_buttons, update_leaderboard.run_if(resource_changed::<LeaderboardSort>))
+                    .chain()
+                    .run_if(in_state(GameState::Leaderboard)),
+            )
+            .add_systems(OnExit(GameState::Leaderboard), despawn_screen);
+    }
+}
+
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum LeaderboardSort {
+    #[default]
+    Score,
+    // longest first
+    Duration,
+    // newest first
+    Date,
+}
+impl LeaderboardSort {
+    const ALL: [LeaderboardSort; 3] = [LeaderboardSort::Score, LeaderboardSort::Duration, LeaderboardSort::Date];
+
+    fn name(self) -> &'static str {
+        match self {
+            LeaderboardSort::Score => "Score",
+            LeaderboardSort::Duration => "Time",
+            LeaderboardSort::Date => "Date",
+        }
+    }
+
+    // with their places, which stay the ones by score
+    fn sort(self, leaderboard: &[RunRecord]) -> Vec<(usize, &RunRecord)> {
+        let mut runs: Vec<_> = leaderboard.iter().enumerate().collect();
+        match self {
+            LeaderboardSort::Score => {}
+            LeaderboardSort::Duration => runs.sort_by(|(_, a), (_, b)| b.seconds.total_cmp(&a.seconds)),
+            LeaderboardSort::Date => runs.sort_by_key(|(_, run)| std::cmp::Reverse(run.date)),
+        }
+        runs
+    }
+}
+
+// a name being typed in, and what it's for
+#[derive(Resource)]
+pub struct NameEntry {
+    target: NameTarget,
+    pub name: String,
+}
+
+enum NameTarget {
+    // the run at that place on the leaderboard
+    Run(usize),
+    NewProfile,
+}
+
+#[derive(Component)]
+struct NameEntryScreen;
+
+#[derive(Component)]
+struct NameEntryText;
+
+#[derive(Component)]
+enum ProfileButton {
+    Next,
+    New,
+}
+
+#[derive(Component)]
+struct ProfileText;
+
+#[derive(Component)]
+enum LeaderboardButton {
+    Sort(LeaderboardSort),
+    Back,
+}
+
+// rebuilt when the sort changes
+#[derive(Component)]
+struct LeaderboardTable;
+
+// arcade style: capitals, digits and a few marks
+fn clean_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_ascii_alphanumeric() || matches!(c, ' ' | '-' | '_' | '.'))
+        .map(|c| c.to_ascii_uppercase())
+        .take(NAME_LENGTH_MAX)
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
+
+// only the presses: a key still held once the name is in mustn't count as pressed again
+fn hold_actions(mut actions: ResMut<ButtonInput<Action>>) {
+    actions.clear();
+}
+
+fn spawn_name_entry(commands: &mut Commands, prompt: String, target: NameTarget) {
+    commands.insert_resource(NameEntry {
+        target,
+        name: String::new(),
+    });
+    commands
+    .spawn((Node {
+        width: Val::Percent(100.0),
+        position_type: PositionType::Absolute,
+        top: Val::Px(120.0),
+        flex_direction: FlexDirection::Column,
+        align_items: AlignItems::Center,
+        ..default()
+    }, NameEntryScreen, Despawn))
+    .with_children(|parent| {
+        parent.spawn((
+            Text::new(prompt),
+            TextColor(Color::srgb(0.9, 0.9, 0.9)),
+        ));
+        parent.spawn((
+            Text::default(),
+            TextFont::from_font_size(40.),
+            TextColor(Color::srgb(0.9, 0.9, 0.9)),
+            NameEntryText,
+        ));
+        parent.spawn((
+            Text::new("enter to keep it, esc to skip"),
+            TextFont::from_font_size(14.),
+            TextColor(Color::srgb(0.6, 0.6, 0.6)),
+        ));
+    });
+}
+
+fn offer_name_entry(mut commands: Commands, save: Res<SaveData>) {
+    if let Some(place) = save.last_place {
+        spawn_name_entry(
+            &mut commands,
+            format!("#{} on the leaderboard! Your name:", place + 1),
+            NameTarget::Run(place),
+        );
+    }
+}
+
+fn close_name_entry(mut commands: Commands, query: Query<Entity, With<NameEntryScreen>>) {
+    close(&mut commands, &query);
+}
+
+fn close(commands: &mut Commands, query: &Query<Entity, With<NameEntryScreen>>) {
+    commands.remove_resource::<NameEntry>();
+    for entity in query {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+// letters come in as text, so the keyboard layout is the player's
+fn type_name(
+    mut commands: Commands,
+    mut keyboard: EventReader<KeyboardInput>,
+    mut entry: ResMut<NameEntry>,
+    mut save: ResMut<SaveData>,
+    query: Query<Entity, With<NameEntryScreen>>,
+) {
+    for input in keyboard.read() {
+        if !input.state.is_pressed() {
+            continue;
+        }
+        let done = match &input.logical_key {
+            Key::Character(text) => {
+                entry.name = clean_name(&(entry.name.clone() + text));
+                false
+            }
+            // trimmed only at the end, so a space can go between words
+            Key::Space => {
+                if entry.name.len() < NAME_LENGTH_MAX {
+                    entry.name.push(' ');
+                }
+                false
+            }
+            Key::Backspace => {
+                entry.name.pop();
+                false
+            }
+            Key::Enter => {
+                // nothing typed keeps the profile's name
+                let name = match clean_name(&entry.name) {
+                    name if name.is_empty() => save.file.profile.clone(),
+                    name => name,
+                };
+                if let NameTarget::Run(place) = entry.target {
+                    save.file.leaderboard[place].name = name.clone();
+                    if let Some(run) = save.file.runs.first_mut() {
+                        run.name = name.clone();
+                    }
+                }
+                save.file.switch_profile(&name);
+                save.write();
+                true
+            }
+            Key::Escape => true,
+            _ => false,
+        };
+        if done {
+            close(&mut commands, &query);
+            return;
+        }
+    }
+}
+
+fn update_name_entry(entry: Res<NameEntry>, mut query: Query<&mut Text, With<NameEntryText>>) {
+    for mut text in &mut query {
+        text.0 = format!("{}_", entry.name);
+    }
+}
+
+fn setup_profiles(mut commands: Commands) {
+    commands
+    .spawn((Node {
+        position_type: PositionType::Absolute,
+        top: Val::Px(40.0),
+        right: Val::Px(30.0),
+        column_gap: Val::Px(10.0),
+        ..default()
+    }, Despawn))
+    .with_children(|parent| {
+        // the name is filled in by update_profiles
+        spawn_button(parent, ButtonSize::Large, Val::Auto, (Text::default(), ProfileText), ProfileButton::Next);
+        spawn_button(parent, ButtonSize::Large, Val::Auto, Text::new("New profile"), ProfileButton::New);
+    });
+}
+
+fn profile_buttons(
+    mut commands: Commands,
+    interaction_query: Query<(&Interaction, &ProfileButton), Changed<Interaction>>,
+    entry: Option<Res<NameEntry>>,
+    mut save: ResMut<SaveData>,
+) {
+    if entry.is_some() {
+        return;
+    }
+    for (interaction, button) in &interaction_query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match button {
+            ProfileButton::Next => {
+                save.file.next_profile();
+                save.write();
+            }
+            ProfileButton::New => spawn_name_entry(&mut commands, "New profile:".to_string(), NameTarget::NewProfile),
+        }
+    }
+}
+
+fn update_profiles(save: Res<SaveData>, mut query: Query<&mut Text, With<ProfileText>>) {
+    for mut text in &mut query {
+        text.0 = format!("Profile: {}", save.file.profile);
+    }
+}
+
+fn setup_leaderboard(mut commands: Commands, mut sort: ResMut<LeaderboardSort>) {
+    // the table is filled in by update_leaderboard
+    *sort = LeaderboardSort::default();
+    commands
+    .spawn((Node {
+        width: Val::Percent(100.0),
+        height: Val::Percent(100.0),
+        flex_direction: FlexDirection::Column,
+        align_items: AlignItems::Center,
+        justify_content: JustifyContent::Center,
+        ..default()
+    }, Despawn))
+    .with_children(|parent| {
+        parent.spawn((
+            Text::new("Leaderboard"),
+            TextColor(Color::srgb(0.9, 0.9, 0.9)),
+        ));
+        parent.spawn(Node::default()).with_children(|row| {
+            for sort in LeaderboardSort::ALL {
+                spawn_button(
+                    row,
+                    ButtonSize::Small,
+                    Val::Px(100.0),
+                    Text::new(sort.name()),
+                    LeaderboardButton::Sort(sort),
+                );
+            }
+        });
+        parent.spawn((
+            Node {
+                display: Display::Grid,
+                grid_template_columns: vec![
+                    GridTrack::px(50.),
+                    GridTrack::px(180.),
+                    GridTrack::px(90.),
+                    GridTrack::px(80.),
+                    GridTrack::px(130.),
+                    GridTrack::px(100.),
+                ],
+                row_gap: Val::Px(4.0),
+                margin: UiRect::vertical(Val::Px(10.0)),
+                ..default()
+            },
+            LeaderboardTable,
+        ));
+        spawn_button(parent, ButtonSize::Small, Val::Auto, Text::new("Back"), LeaderboardButton::Back);
+    });
+}
+
+fn leaderboard_buttons(
+    interaction_query: Query<(&Interaction, &LeaderboardButton), Changed<Interaction>>,
+    mut sort: ResMut<LeaderboardSort>,
+    actions: Res<ButtonInput<Action>>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    for (interaction, button) in &interaction_query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match button {
+            LeaderboardButton::Sort(by) => *sort = *by,
+            LeaderboardButton::Back => game_state.set(GameState::Menu),
+        }
+    }
+    if actions.just_pressed(Action::Back) {
+        game_state.set(GameState::Menu);
+    }
+}
+
+// the current profile's runs stand out
+fn update_leaderboard(
+    mut commands: Commands,
+    query: Query<Entity, With<LeaderboardTable>>,
+    save: Res<SaveData>,
+    sort: Res<LeaderboardSort>,
+    settings: Res<Settings>,
+) {
+    let dim = settings.file.palette().dim_text;
+    for table in &query {
+        commands.entity(table).despawn_descendants().with_children(|table| {
+            for header in ["#", "Name", "Score", "Time", "Date", "Mode"] {
+                table.spawn((Text::new(header), TextFont::from_font_size(16.), TextColor(dim)));
+            }
+            if save.file.leaderboard.is_empty() {
+                table.spawn((Text::new("no runs yet"), TextFont::from_font_size(16.), TextColor(dim)));
+            }
+            for (place, run) in sort.sort(&save.file.leaderboard) {
+                let color = if run.name == save.file.profile { Color::srgb(0.9, 0.9, 0.9) } else { dim };
+                let date = DateTime::from_timestamp(run.date as i64, 0)
+                    .map(|date| date.with_timezone(&Local).format("%Y-%m-%d").to_string())
+                    .unwrap_or_default();
+                let seconds = run.seconds as u32;
+                for cell in [
+                    format!("{}", place + 1),
+                    run.name.clone(),
+                    format!("{}", run.score),
+                    format!("{}:{:02}", seconds / 60, seconds % 60),
+                    date,
+                    run.mode.name().to_string(),
+                ] {
+                    table.spawn((Text::new(cell), TextFont::from_font_size(16.), TextColor(color)));
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::difficulty::GameMode;
+
+    fn run(score: u32, seconds: f32, date: u64) -> RunRecord {
+        RunRecord {
+            score,
+            seconds,
+            date,
+            name: String::new(),
+            mode: GameMode::Classic,
+        }
+    }
+
+    #[test]
+    fn sorting_keeps_the_places() {
+        let leaderboard = [run(300, 20., 5), run(200, 40., 9), run(100, 30., 1)];
+        let places = |sort: LeaderboardSort| sort.sort(&leaderboard).iter().map(|(place, _)| *place).collect::<Vec<_>>();
+        assert_eq!(places(LeaderboardSort::Score), [0, 1, 2]);
+        assert_eq!(places(LeaderboardSort::Duration), [1, 2, 0]);
+        assert_eq!(places(LeaderboardSort::Date), [1, 0, 2]);
+    }
+
+    #[test]
+    fn names_are_cleaned_up() {
+        assert_eq!(clean_name("  ann lee "), "ANN LEE");
+        assert_eq!(clean_name("a\u{e9}b!c"), "ABC");
+        assert_eq!(clean_name("abcdefghijklmnop"), "ABCDEFGHIJKL");
+    }
+}
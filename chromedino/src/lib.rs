@@ -0,0 +1,741 @@
+// mostly followed this tutorial: https://github.com/Biped-Potato/flappy_bird/blob/master/src/main.rs
+
+use bevy::prelude::*;
+use bevy::app::RunFixedMainLoopSystem;
+use bevy::render::view::RenderLayers;
+
+pub mod actions;
+pub mod art;
+pub mod autoplay;
+pub mod choreography;
+pub mod collision;
+pub mod daily;
+pub mod difficulty;
+pub mod ghost;
+pub mod gym;
+pub mod hud;
+pub mod leaderboard;
+pub mod patterns;
+pub mod pause;
+pub mod power_ups;
+pub mod replay;
+pub mod rng;
+pub mod score;
+pub mod settings;
+pub mod sound;
+pub mod tuning;
+pub mod view;
+use actions::Action;
+use collision::{Collision, Hitbox};
+use difficulty::{Difficulty, GameMode};
+use patterns::PatternSpawner;
+use power_ups::{Pickup, PowerUp, PowerUps};
+use rng::GameRng;
+use score::{RunScore, SaveData};
+use settings::{Palette, Settings};
+use tuning::{JumpTuning, Tuning};
+
+// the rest of the numbers are in assets/game.tuning.ron
+
+// game
+pub const TICKS_PER_SECOND: f64 = 120.;
+pub const PLAIN_HEIGHT: f32 = 0.;
+
+// dino
+pub const DINO_HEIGHT: f32 = 60.;
+pub const DINO_WIDTH: f32 = 20.;
+pub const DINO_DUCK_WIDTH: f32 = 28.;
+pub const DINO_DUCK_HEIGHT: f32 = 30.;
+
+// the whole game minus the window, so it also runs headless under MinimalPlugins
+// (which needs StatesPlugin and InputPlugin added next to it)
+#[derive(Default)]
+pub struct GamePlugin {
+    pub config: GameConfig,
+}
+impl Plugin for GamePlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .insert_resource(self.config.clone())
+            .init_state::<GameState>()
+            .add_computed_state::<InRun>()
+            .add_sub_state::<RunState>()
+
+            .add_plugins((
+                // the run itself and what it runs into
+                (GameplayPlugin, collision::CollisionPlugin, power_ups::PowerUpPlugin),
+                actions::ActionsPlugin,
+                // what it's drawn with and how much of it fits the window
+                (art::ArtPlugin, view::ViewPlugin),
+                autoplay::AutoplayPlugin,
+                patterns::PatternPlugin,
+                pause::PausePlugin,
+                hud::HudPlugin,
+                choreography::ChoreographyPlugin,
+                // what a run plays out from: the seed and the mode
+                (rng::RngPlugin, difficulty::DifficultyPlugin, daily::DailyPlugin),
+                tuning::TuningPlugin,
+                // the scores, and who got them
+                (score::ScorePlugin { save_to_disk: self.config.save_to_disk }, leaderboard::LeaderboardPlugin),
+                settings::SettingsPlugin { save_to_disk: self.config.save_to_disk },
+                sound::SoundPlugin,
+                // the runs that get played back: watched, or raced against
+                (
+                    replay::ReplayPlugin { save_to_disk: self.config.save_to_disk },
+                    ghost::GhostPlugin { save_to_disk: self.config.save_to_disk },
+                ),
+            ))
+
+            // update every state
+            .add_systems(Update, hover_buttons)
+
+            // starter menu
+            .add_systems(OnEnter(GameState::Menu), setup_menu)
+            .add_systems(Update, menu_buttons.run_if(in_state(GameState::Menu)))
+            .add_systems(OnExit(GameState::Menu), despawn_screen)
+            // death screen
+            .add_systems(OnEnter(GameState::Dead), setup_death_screen)
+            .add_systems(Update, end_game_button.run_if(in_state(GameState::Dead)))
+            .add_systems(OnExit(GameState::Dead), despawn_screen);
+    }
+}
+
+// the run itself: dino, obstacles, speed and camera
+pub struct GameplayPlugin;
+impl Plugin for GameplayPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<DinoInput>()
+            .init_resource::<RunTick>()
+            // all gameplay runs in FixedUpdate, so it plays out the same at any frame rate
+            .insert_resource(Time::<Fixed>::from_hz(TICKS_PER_SECOND))
+            .configure_sets(FixedUpdate, (DinoInputSet, GameplaySet).chain().run_if(in_state(RunState::Running).and(run_is_live)))
+            .add_systems(Startup, setup_canvas)
+            .add_systems(Update, retime_game_speed.run_if(resource_changed::<Tuning>))
+
+            // game (or a replay of one)
+            .add_systems(OnEnter(InRun), (setup_player, rng::reseed))
+            .add_systems(RunFixedMainLoop, read_dino_input.in_set(RunFixedMainLoopSystem::BeforeFixedMainLoop).run_if(in_state(GameState::Game).and(in_state(RunState::Running))))
+            .add_systems(FixedFirst, store_previous_positions.run_if(in_state(InRun)))
+            .add_systems(FixedUpdate, (update_game_speed, update_obstacles, update_dino, collision::detect_collisions, die_on_hit, advance_tick).chain().in_set(GameplaySet))
+            .add_systems(RunFixedMainLoop, interpolate_positions.in_set(RunFixedMainLoopSystem::AfterFixedMainLoop).run_if(in_state(InRun)));
+    }
+}
+
+#[derive(Resource, Clone)]
+pub struct GameConfig {
+    // the part of the world that's always in view, see view.rs; the window is opened at this size
+    pub play_field: Vec2,
+    // off for tests, so they don't touch the real save file and replays
+    pub save_to_disk: bool,
+}
+impl Default for GameConfig {
+    fn default() -> Self {
+        GameConfig {
+            play_field: Vec2::new(1280., 720.),
+            save_to_disk: true,
+        }
+    }
+}
+
+#[derive(Component)]
+struct Despawn;
+
+// the camera the game is drawn with (and the one that spins), not the ui one
+#[derive(Component)]
+struct WorldCamera;
+
+// nothing in the world is on this layer, the ui camera only draws ui
+const UI_LAYER: usize = 1;
+
+#[derive(Component, PartialEq, States, Debug, Hash, Eq, Clone, Default)]
+pub enum GameState{
+    #[default]
+    Menu,
+    Game,
+    Replay,
+    // attract mode: the autopilot plays behind the menu, see autoplay.rs
+    Demo,
+    Dead,
+    // rebinding, see actions.rs
+    Controls,
+    // options, see settings.rs
+    Settings,
+    // the best runs of every profile, see leaderboard.rs
+    Leaderboard,
+}
+
+// a run is being played, by the player, a replay or the demo
+#[derive(PartialEq, Debug, Hash, Eq, Clone)]
+pub struct InRun;
+impl ComputedStates for InRun {
+    type SourceStates = GameState;
+    fn compute(sources: GameState) -> Option<Self> {
+        matches!(sources, GameState::Game | GameState::Replay | GameState::Demo).then_some(InRun)
+    }
+}
+
+// only exists during a run, and every run starts out running
+#[derive(SubStates, PartialEq, Debug, Hash, Eq, Clone, Default)]
+#[source(InRun = InRun)]
+pub enum RunState {
+    #[default]
+    Running,
+    Paused,
+}
+
+// what the dino is told to do on the next tick; filled from the keyboard or from a replay
+// presses are kept until a tick uses them, a frame can have no ticks at all
+#[derive(Resource, Default, Clone, Copy, PartialEq, Debug)]
+pub struct DinoInput {
+    pub jump: bool,
+    // held, not pressed
+    pub jump_held: bool,
+    pub duck: bool,
+}
+
+// fixed update: the input for this tick is decided, then the gameplay runs on it
+#[derive(SystemSet, Debug, Hash, PartialEq, Eq, Clone)]
+struct DinoInputSet;
+#[derive(SystemSet, Debug, Hash, PartialEq, Eq, Clone)]
+struct GameplaySet;
+
+// fixed ticks since the run started
+#[derive(Resource, Default, Deref, DerefMut)]
+pub struct RunTick(pub u32);
+
+// where the physics puts things; Transform only follows it for drawing
+#[derive(Component, Default, Clone, Copy, Deref, DerefMut)]
+pub struct Position(pub Vec2);
+
+// Position at the start of the current tick, drawing blends between the two
+#[derive(Component, Default, Clone, Copy, Deref, DerefMut)]
+struct PreviousPosition(Vec2);
+
+#[derive(Component)]
+enum ButtonType {
+    Play,
+    Controls,
+    Settings,
+    Leaderboard,
+    Exit,
+}
+
+#[derive(Component, Default)]
+pub struct Dino {
+    pub velocity: f32,
+    pub jumped: bool,
+    pub ducking: bool,
+    // seconds since it was last on the ground (coyote time); ducking drops it from standing height, off the ground
+    // for a moment without jumping
+    pub air_time: f32,
+    // seconds an early jump press is still waiting for a landing (jump buffer)
+    pub buffered_jump: f32,
+    // used the double jump power up since it took off
+    pub air_jumped: bool,
+}
+impl Dino {
+    // one tick of the jump, the duck and gravity; the ghost (ghost.rs) runs on this too. returns the new size
+    pub fn step(&mut self, position: &mut Vec2, input: &DinoInput, tuning: &JumpTuning, power_ups: &PowerUps, delta: f32) -> Vec2 {
+        if input.jump {
+            self.buffered_jump = tuning.jump_buffer_seconds;
+        }
+        let can_jump = !self.jumped && self.air_time <= tuning.coyote_seconds;
+        if (input.jump || self.buffered_jump > 0.) && can_jump {
+            self.jumped = true;
+            self.velocity = tuning.jump_force;
+            self.buffered_jump = 0.;
+        } else if input.jump && self.jumped && !self.air_jumped && power_ups.is_active(PowerUp::DoubleJump) {
+            self.air_jumped = true;
+            self.velocity = tuning.jump_force;
+        }
+        self.buffered_jump = (self.buffered_jump - delta).max(0.);
+
+        // let go early, land early
+        if self.jumped && !input.jump_held && self.velocity > tuning.jump_cut_velocity {
+            self.velocity = tuning.jump_cut_velocity;
+        }
+
+        // only on the ground; holding it through a landing ducks right away
+        self.ducking = input.duck && !self.jumped;
+        let size = self.size();
+
+        // down in the air is a fast fall
+        let gravity = if input.duck && self.jumped {
+            tuning.gravity * tuning.fast_fall_gravity_scale
+        } else {
+            tuning.gravity
+        };
+        self.velocity -= delta * gravity;
+        position.y += self.velocity * delta;
+        self.air_time += delta;
+
+        // reset on ground; a jump out of a duck starts below it, standing up pushes it out without stopping it
+        if position.y < PLAIN_HEIGHT + size.y / 2. {
+            if self.velocity <= 0. {
+                self.velocity = 0.;
+                self.jumped = false;
+                self.air_jumped = false;
+                self.air_time = 0.;
+            }
+            position.y = PLAIN_HEIGHT + size.y / 2.;
+        }
+        size
+    }
+
+    pub fn size(&self) -> Vec2 {
+        if self.ducking {
+            Vec2::new(DINO_DUCK_WIDTH, DINO_DUCK_HEIGHT)
+        } else {
+            Vec2::new(DINO_WIDTH, DINO_HEIGHT)
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct Obstacle;
+
+#[derive(Component, Clone, Copy, PartialEq, Debug)]
+pub enum ObstacleKind {
+    Ground,
+    Flying,
+}
+impl ObstacleKind {
+    fn color(&self, palette: &Palette) -> Color {
+        match self {
+            ObstacleKind::Ground => palette.ground_obstacle,
+            ObstacleKind::Flying => palette.flying_obstacle,
+        }
+    }
+
+    // a cactus fills its box, a flyer's corners are air
+    pub fn hitbox(&self, size: Vec2) -> Hitbox {
+        match self {
+            ObstacleKind::Ground => Hitbox::rect(size),
+            ObstacleKind::Flying => Hitbox::capsule(size),
+        }
+    }
+}
+
+#[derive(Resource, Deref, DerefMut)]
+struct GameSpeedTimer(Timer);
+
+#[derive(Resource)]
+pub struct GameManager{
+    pub play_field: Vec2,
+    pub game_speed: f32,
+    pub difficulty: Difficulty,
+}
+
+fn setup_canvas(
+    mut commands: Commands,
+    config: Res<GameConfig>,
+    tuning: Res<Tuning>,
+    settings: Res<Settings>,
+) {
+    // camera
+    commands.spawn((Camera2d, Transform {..default()}, WorldCamera));
+    // ui gets its own camera on top, so it stays upright
+    commands.spawn((
+        Camera2d,
+        Camera { order: 1, clear_color: ClearColorConfig::None, ..default() },
+        IsDefaultUiCamera,
+        RenderLayers::layer(UI_LAYER),
+    ));
+    // background color
+    commands.insert_resource(ClearColor(settings.file.palette().background));
+
+    // game_manager
+    commands.insert_resource(GameManager {play_field: config.play_field, game_speed: 1., difficulty: default()});
+
+    // game speed timer
+    commands.insert_resource(GameSpeedTimer(Timer::from_seconds(tuning.speed.seconds_until_full_speed, TimerMode::Once)));
+}
+
+// the menu's buttons, and the smaller ones of the screens with rows of them
+#[derive(Clone, Copy)]
+pub(crate) enum ButtonSize {
+    Large,
+    Small,
+}
+
+// a bordered button with its label, the same on every screen; hover_buttons does the rest
+pub(crate) fn spawn_button(
+    parent: &mut ChildBuilder,
+    size: ButtonSize,
+    width: Val,
+    label: impl Bundle,
+    bundle: impl Bundle,
+) {
+    let (height, border, padding, margin) = match size {
+        ButtonSize::Large => (50.0, 5.0, UiRect::all(Val::Px(10.0)), UiRect::ZERO),
+        ButtonSize::Small => (40.0, 3.0, UiRect::horizontal(Val::Px(10.0)), UiRect::all(Val::Px(5.0))),
+    };
+    parent.spawn((
+        Button,
+        Node {
+            width,
+            height: Val::Px(height),
+            // rectangle border
+            border: UiRect::all(Val::Px(border)),
+            // center the text
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            margin,
+            padding,
+            ..default()
+        },
+        BorderColor(Color::WHITE),
+        BackgroundColor(Color::BLACK),
+        BorderRadius::all(Val::Px(10.0)),
+        bundle,
+    ))
+    .with_child((label, TextColor(Color::srgb(0.9, 0.9, 0.9))));
+}
+
+fn hover_buttons(
+    mut interaction_query: Query<
+        (&Interaction, &mut BackgroundColor, &mut BorderColor, &Children),
+        (Changed<Interaction>, With<Button>)
+    >,
+    mut text_color_query: Query<&mut TextColor>,
+) {
+    for (interaction, mut background_color, mut border_color, children) in &mut interaction_query {
+        let mut text_color = text_color_query.get_mut(children[0]).unwrap();
+        match *interaction {
+            Interaction::Hovered => {
+                *background_color = BackgroundColor(Color::WHITE).into();
+                border_color.0 = Color::BLACK;
+                **text_color = *TextColor(Color::BLACK);
+            }
+            Interaction::None => {
+                *background_color = BackgroundColor(Color::BLACK).into();
+                border_color.0 = Color::WHITE;
+                **text_color = *TextColor(Color::WHITE);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn setup_menu(
+    mut commands: Commands,
+    save: Res<SaveData>,
+) {
+    // best score on top
+    commands
+    .spawn((Node {
+        width: Val::Percent(100.0),
+        position_type: PositionType::Absolute,
+        top: Val::Px(40.0),
+        justify_content: JustifyContent::Center,
+        ..default()
+    }, Despawn))
+    .with_child((
+        Text::new(format!("Best: {}", save.file.best_score)),
+        TextColor(Color::srgb(0.9, 0.9, 0.9)),
+    ));
+
+    commands
+    // center ui
+    .spawn((Node {
+        width: Val::Percent(100.0),
+        height: Val::Percent(100.0),
+        align_items: AlignItems::Center,
+        justify_content: JustifyContent::Center,
+        // space between the buttons
+        column_gap: Val::Px(40.0),
+        ..default()
+    }, Despawn))
+    .with_children(|parent| {
+        spawn_button(parent, ButtonSize::Large, Val::Px(100.0), Text::new("Start"), ButtonType::Play);
+        spawn_button(parent, ButtonSize::Large, Val::Auto, Text::new("Controls"), ButtonType::Controls);
+        spawn_button(parent, ButtonSize::Large, Val::Auto, Text::new("Settings"), ButtonType::Settings);
+        spawn_button(parent, ButtonSize::Large, Val::Auto, Text::new("Leaderboard"), ButtonType::Leaderboard);
+        spawn_button(parent, ButtonSize::Large, Val::Auto, Text::new("Exit Game"), ButtonType::Exit);
+    });
+}
+
+fn menu_buttons(
+    mut game_state: ResMut<NextState<GameState>>,
+    interaction_query: Query< (&Interaction, &ButtonType), (Changed<Interaction>, With<Button>), >,
+    actions: Res<ButtonInput<Action>>,
+    mut exit: EventWriter<AppExit>,
+) {
+    for (interaction, button_type) in &interaction_query {
+        if *interaction == Interaction::Pressed {
+            match button_type {
+                ButtonType::Play => game_state.set(GameState::Game),
+                ButtonType::Controls => game_state.set(GameState::Controls),
+                ButtonType::Settings => game_state.set(GameState::Settings),
+                ButtonType::Leaderboard => game_state.set(GameState::Leaderboard),
+                ButtonType::Exit => {exit.send(AppExit::Success);},
+            }
+        }
+    }
+    if actions.just_pressed(Action::Confirm){
+        game_state.set(GameState::Game);
+    }
+    if actions.just_pressed(Action::Back){
+        exit.send(AppExit::Success);
+    }
+}
+
+fn update_game_speed(
+    mut game_manager: ResMut<GameManager>,
+    time: Res<Time>,
+    mut timer: ResMut<GameSpeedTimer>,
+    tuning: Res<Tuning>,
+    mode: Res<GameMode>,
+    power_ups: Res<PowerUps>,
+) {
+    timer.tick(time.delta());
+    // the mode picks the curves, see difficulty.rs
+    game_manager.difficulty = tuning.difficulty.curves(*mode).sample(timer.fraction());
+    game_manager.game_speed = game_manager.difficulty.speed * power_ups.speed_scale(&tuning.power_ups);
+}
+
+// the timer was made with the old duration; a running one keeps its elapsed time
+fn retime_game_speed(
+    tuning: Res<Tuning>,
+    mut game_speed_timer: ResMut<GameSpeedTimer>,
+) {
+    game_speed_timer.set_duration(std::time::Duration::from_secs_f32(tuning.speed.seconds_until_full_speed));
+}
+
+fn setup_player(
+    mut commands: Commands,
+    game_manager: Res<GameManager>,
+    mut game_speed_timer: ResMut<GameSpeedTimer>,
+    mut run_tick: ResMut<RunTick>,
+    mut input: ResMut<DinoInput>,
+    settings: Res<Settings>,
+) {
+    let palette = settings.file.palette();
+    let dino_position = Vec2::new(0., PLAIN_HEIGHT + DINO_HEIGHT / 2.);
+    // player
+    commands.spawn((
+        // a rectangle of color until art.rs dresses it up
+        Sprite::from_color(palette.dino, Vec2::new(DINO_WIDTH,DINO_HEIGHT)),
+        Transform::from_translation(dino_position.extend(0.0)),
+        Dino::default(),
+        Hitbox::rect(Vec2::new(DINO_WIDTH,DINO_HEIGHT)),
+        Position(dino_position),
+        PreviousPosition(dino_position),
+        Despawn
+    ));
+    
+    // plain line
+    commands.spawn((
+        Sprite::from_color(palette.ground,Vec2::new(view::reach(game_manager.play_field) * 2., 1.)),
+        Transform::from_xyz(0., PLAIN_HEIGHT, 0.),
+        Despawn)
+    );
+
+    **run_tick = 0;
+    *input = DinoInput::default();
+    game_speed_timer.reset();
+}
+
+// a dead dino can still get a few more ticks in before the state changes at the start of the next frame
+fn run_is_live(next_state: Res<NextState<GameState>>) -> bool {
+    !matches!(*next_state, NextState::Pending(_))
+}
+
+fn read_dino_input(
+    mut input: ResMut<DinoInput>,
+    actions: Res<ButtonInput<Action>>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    input.jump |= actions.just_pressed(Action::Jump);
+    input.jump_held = actions.pressed(Action::Jump);
+    input.duck = actions.pressed(Action::Duck);
+
+    // debug kill
+    if actions.just_pressed(Action::DebugKill){
+        game_state.set(GameState::Dead);
+    }
+}
+
+fn update_dino(
+    mut dino_query: Query<(&mut Dino,&mut Position,&mut Hitbox),(With<Dino>,Without<Obstacle>)>,
+    time: Res<Time>,
+    input: Res<DinoInput>,
+    tuning: Res<Tuning>,
+    power_ups: Res<PowerUps>,
+) {
+    if let Ok((mut dino, mut position, mut hitbox)) = dino_query.get_single_mut() {
+        let size = dino.step(&mut position.0, &input, &tuning.jump, &power_ups, time.delta_secs());
+        *hitbox = Hitbox::rect(size);
+    }
+}
+
+// whatever it ran into, see collision.rs
+fn die_on_hit(
+    mut commands: Commands,
+    mut collisions: EventReader<Collision>,
+    mut power_ups: ResMut<PowerUps>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    for collision in collisions.read() {
+        let Collision::Hit { obstacle } = *collision else { continue };
+        // a shield breaks the obstacle instead, once
+        if power_ups.take(PowerUp::Shield) {
+            commands.entity(obstacle).despawn();
+            continue;
+        }
+        game_state.set(GameState::Dead);
+        break;
+    }
+}
+
+// what moves with the ground: obstacles and the pickups between them
+type Scrolling = Or<(With<Obstacle>, With<Pickup>)>;
+
+fn update_obstacles(
+    mut commands: Commands,
+    mut obstacle_query: Query<(Entity, &mut Position, &Hitbox), Scrolling>,
+    game_manager: Res<GameManager>,
+    time: Res<Time>,
+    mut run_score: ResMut<RunScore>,
+    mut spawner: ResMut<PatternSpawner>,
+    tuning: Res<Tuning>,
+) {
+    let scrolled = time.delta_secs() * tuning.speed.scroll_speed(game_manager.game_speed);
+    run_score.distance += scrolled;
+    // the layout ahead scrolls with them, see patterns.rs
+    spawner.scroll(scrolled);
+    // out of sight however the camera is turned, see view.rs
+    let reach = view::reach(game_manager.play_field);
+    for (entity, mut position, hitbox) in obstacle_query.iter_mut() {
+        position.x -= scrolled;
+        // out of bounds
+        let width = hitbox.size().x;
+        if position.x + width / 2. < -reach {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+fn advance_tick(mut run_tick: ResMut<RunTick>, mut input: ResMut<DinoInput>) {
+    **run_tick += 1;
+    // this tick used the press up
+    input.jump = false;
+}
+
+fn store_previous_positions(mut query: Query<(&Position, &mut PreviousPosition)>) {
+    for (position, mut previous) in &mut query {
+        **previous = **position;
+    }
+}
+
+// draw things where they'd be between the last two ticks, otherwise movement stutters
+fn interpolate_positions(
+    mut query: Query<(&Position, &PreviousPosition, &mut Transform)>,
+    time: Res<Time<Fixed>>,
+) {
+    let overstep = time.overstep_fraction();
+    for (position, previous, mut transform) in &mut query {
+        let drawn = previous.lerp(**position, overstep);
+        transform.translation.x = drawn.x;
+        transform.translation.y = drawn.y;
+    }
+}
+
+fn setup_death_screen(
+    mut commands: Commands,
+    run_score: Res<RunScore>,
+    save: Res<SaveData>,
+    game_rng: Res<GameRng>,
+    settings: Res<Settings>,
+) {
+    commands
+    .spawn((Node {
+        width: Val::Percent(100.0),
+        height: Val::Percent(85.0),
+        flex_direction: FlexDirection::Column,
+        // flex-dir flips these two values
+        align_items: AlignItems::Center,
+        justify_content: JustifyContent::FlexEnd,
+        ..default()
+    }, Despawn))
+    .with_children(|parent| {
+        parent.spawn((
+            Text::new("You died"), 
+            TextColor(Color::srgb(1., 0., 0.)), 
+        ));
+        parent.spawn((
+            Text::new(if save.last_run_was_best {
+                format!("New best: {}", run_score.score())
+            } else {
+                format!("Score: {}  Best: {}", run_score.score(), save.best())
+            }),
+            TextColor(Color::srgb(0.9, 0.9, 0.9)),
+        ));
+        parent.spawn((
+            Text::new(format!("Seed: {}", game_rng.seed)),
+            TextFont::from_font_size(14.),
+            TextColor(settings.file.palette().dim_text),
+        ));
+        parent.spawn(Node {
+            // width: Val::Percent(100.0),
+            // height: Val::Percent(100.0),
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            margin: UiRect::all(Val::Px(20.0)),
+            column_gap: Val::Px(40.0),
+            ..default()
+        })
+        .with_children(|parent| {
+            spawn_button(parent, ButtonSize::Large, Val::Auto, Text::new("Play again"), ButtonType::Play);
+            spawn_button(parent, ButtonSize::Large, Val::Px(100.0), Text::new("Menu"), ButtonType::Exit);
+        });
+    });
+}
+
+fn end_game_button(
+    mut game_state: ResMut<NextState<GameState>>,
+    interaction_query: Query< (&Interaction, &ButtonType), (Changed<Interaction>, With<Button>), >,
+    actions: Res<ButtonInput<Action>>,
+) {
+    for (interaction, button_type) in &interaction_query {
+        if *interaction == Interaction::Pressed {
+            match button_type {
+                ButtonType::Play => game_state.set(GameState::Game),
+                ButtonType::Exit => game_state.set(GameState::Menu),
+                ButtonType::Controls => game_state.set(GameState::Controls),
+                ButtonType::Settings => game_state.set(GameState::Settings),
+                ButtonType::Leaderboard => game_state.set(GameState::Leaderboard),
+            }
+        }
+    }
+    if actions.just_pressed(Action::Confirm){
+        game_state.set(GameState::Game);
+    }
+    if actions.just_pressed(Action::Back){
+        game_state.set(GameState::Menu);
+    }
+}
+
+// the value of `--name value` or `--name=value` on the command line
+fn arg_value(name: &str) -> Option<String> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == name {
+            return args.next();
+        }
+        if let Some(value) = arg.strip_prefix(name).and_then(|v| v.strip_prefix('=')) {
+            return Some(value.to_string());
+        }
+    }
+    None
+}
+
+fn despawn_screen(
+    mut commands: Commands,
+    query: Query<Entity, With<Despawn>>,
+) {
+    for entity in &query {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,609 @@
+use bevy::prelude::*;
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::difficulty::{Difficulty, GameMode};
+use crate::power_ups;
+use crate::rng::{self, GameRng};
+use crate::settings::{Palette, Settings};
+use crate::tuning::{JumpTuning, ObstacleTuning, Tuning};
+use crate::view;
+use crate::{
+    Despawn, GameManager, GameplaySet, InRun, Obstacle, ObstacleKind, Position, PreviousPosition,
+    DINO_HEIGHT, DINO_WIDTH, PLAIN_HEIGHT, TICKS_PER_SECOND,
+};
+
+// the game steps the jump once a tick, which comes out a bit lower than the real arc
+pub(crate) const JUMP_MARGIN_HEIGHT: f32 = 6.;
+// takeoffs and landings only happen on ticks, so every window loses this many ticks of scrolling on each side
+pub(crate) const JUMP_MARGIN_TICKS: f32 = 2.;
+// a layout that can't be cleared is pushed back this far at a time...
+const REPAIR_PUSH: f32 = 25.;
+const REPAIR_TRIES: u32 = 40;
+// ...and loses its last obstacle if that's not enough
+
+// obstacles come in patterns: singles, doubles, triples, staircases and the ones from the tuning file.
+// every pattern is checked against the jump before it's placed, so a perfect player can always make it
+pub struct PatternPlugin;
+impl Plugin for PatternPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PatternSpawner>()
+            .add_systems(OnEnter(InRun), setup_patterns.after(rng::reseed))
+            .add_systems(
+                FixedUpdate,
+                spawn_patterns
+                    .in_set(GameplaySet)
+                    .after(crate::update_obstacles)
+                    .before(crate::update_dino),
+            );
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct PatternTuning {
+    // at the top of the patterns difficulty curve; the rest are single obstacles
+    pub cluster_chance_max: f32,
+    // how often each kind of cluster comes up, next to each other and the authored ones
+    pub double_weight: f32,
+    pub triple_weight: f32,
+    pub staircase_weight: f32,
+    // the most space between the obstacles of a double or triple
+    pub cluster_gap_max: f32,
+    pub authored: Vec<AuthoredPattern>,
+}
+impl Default for PatternTuning {
+    fn default() -> Self {
+        PatternTuning {
+            cluster_chance_max: 0.5,
+            double_weight: 1.,
+            triple_weight: 1.,
+            staircase_weight: 0.5,
+            cluster_gap_max: 12.,
+            authored: vec![AuthoredPattern {
+                weight: 0.5,
+                obstacles: vec![
+                    AuthoredObstacle::ground(0., Vec2::new(20., 30.)),
+                    AuthoredObstacle::ground(24., Vec2::new(24., 60.)),
+                    AuthoredObstacle::ground(52., Vec2::new(20., 30.)),
+                ],
+            }],
+        }
+    }
+}
+impl PatternTuning {
+    pub fn validate(&self) -> Result<(), String> {
+        let weights = [self.double_weight, self.triple_weight, self.staircase_weight];
+        if weights.into_iter().chain(self.authored.iter().map(|pattern| pattern.weight)).any(|weight| weight < 0.) {
+            return Err("patterns weights can't be negative".to_string());
+        }
+        if self.cluster_gap_max < 0. {
+            return Err("patterns.cluster_gap_max can't be negative".to_string());
+        }
+        for pattern in &self.authored {
+            if pattern.obstacles.is_empty() {
+                return Err("every pattern in patterns.authored needs an obstacle".to_string());
+            }
+            if pattern.obstacles.iter().any(|obstacle| obstacle.size.min_element() <= 0. || obstacle.bottom < 0.) {
+                return Err("obstacles in patterns.authored need a size above 0 and can't be below the ground".to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AuthoredPattern {
+    pub weight: f32,
+    pub obstacles: Vec<AuthoredObstacle>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AuthoredObstacle {
+    // left edge, from the start of the pattern
+    pub x: f32,
+    // above the ground, anything but 0 is a flyer
+    #[serde(default)]
+    pub bottom: f32,
+    pub size: Vec2,
+}
+impl AuthoredObstacle {
+    fn ground(x: f32, size: Vec2) -> Self {
+        AuthoredObstacle { x, bottom: 0., size }
+    }
+}
+
+// an obstacle of a pattern, from the pattern's left edge
+#[derive(Clone, PartialEq, Debug)]
+struct PatternObstacle {
+    x: f32,
+    bottom: f32,
+    size: Vec2,
+}
+impl PatternObstacle {
+    fn kind(&self) -> ObstacleKind {
+        if self.bottom > 0. {
+            ObstacleKind::Flying
+        } else {
+            ObstacleKind::Ground
+        }
+    }
+}
+
+// an obstacle laid out on the screen, ready to spawn
+#[derive(Clone, PartialEq, Debug)]
+pub struct PlacedObstacle {
+    pub kind: ObstacleKind,
+    // the center, like Position
+    pub position: Vec2,
+    pub size: Vec2,
+}
+impl PlacedObstacle {
+    fn left(&self) -> f32 {
+        self.position.x - self.size.x / 2.
+    }
+
+    fn right(&self) -> f32 {
+        self.position.x + self.size.x / 2.
+    }
+
+    fn bottom(&self) -> f32 {
+        self.position.y - self.size.y / 2. - PLAIN_HEIGHT
+    }
+
+    fn top(&self) -> f32 {
+        self.position.y + self.size.y / 2. - PLAIN_HEIGHT
+    }
+}
+
+// the jump a perfect player makes: held all the way up, no coyote time, no fast fall
+pub(crate) struct JumpArc {
+    force: f32,
+    gravity: f32,
+}
+impl JumpArc {
+    pub(crate) fn new(tuning: &JumpTuning) -> Self {
+        JumpArc {
+            force: tuning.jump_force,
+            gravity: tuning.gravity,
+        }
+    }
+
+    pub(crate) fn airtime(&self) -> f32 {
+        2. * self.force / self.gravity
+    }
+
+    // seconds after takeoff the feet go above `height` and come back down past it, none if the jump doesn't get there
+    pub(crate) fn above(&self, height: f32) -> Option<(f32, f32)> {
+        let discriminant = self.force * self.force - 2. * self.gravity * height;
+        if discriminant <= 0. {
+            return None;
+        }
+        let root = discriminant.sqrt();
+        Some(((self.force - root) / self.gravity, (self.force + root) / self.gravity))
+    }
+}
+
+// where the dino can land after `obstacles` (sorted by left edge) at `speed`, none if it can't get past them.
+// everything is in screen x of the dino's left edge, as if the obstacles stood still and the dino ran at them;
+// `landing` is the earliest the dino can take off again after what came before
+fn fit(arc: &JumpArc, speed: f32, landing: f32, obstacles: &[PlacedObstacle]) -> Option<f32> {
+    let margin = JUMP_MARGIN_TICKS * speed / TICKS_PER_SECOND as f32;
+    // where the dino's left edge is when it starts and stops touching an obstacle
+    let touching = |obstacle: &PlacedObstacle| (obstacle.left() - DINO_WIDTH - margin, obstacle.right() + margin);
+    let down_before_next = |landed: f32, next: Option<&PlacedObstacle>| next.is_none_or(|next| landed <= touching(next).0);
+
+    // the earliest landing after the first i obstacles
+    let mut earliest: Vec<Option<f32>> = vec![None; obstacles.len() + 1];
+    earliest[0] = Some(landing);
+    let keep_earliest = |earliest: &mut Vec<Option<f32>>, i: usize, landed: f32| {
+        earliest[i] = Some(earliest[i].map_or(landed, |other| other.min(landed)));
+    };
+
+    for i in 0..obstacles.len() {
+        let Some(landing) = earliest[i] else { continue };
+
+        // too high to hit standing up, but jumping under it isn't safe
+        if obstacles[i].bottom() >= DINO_HEIGHT {
+            let (from, to) = touching(&obstacles[i]);
+            if landing <= from {
+                keep_earliest(&mut earliest, i + 1, landing.max(to));
+            }
+            continue;
+        }
+
+        // one jump over obstacles i..=j, taking off as early as it can
+        let (mut earliest_takeoff, mut latest_takeoff) = (f32::NEG_INFINITY, f32::INFINITY);
+        for j in i..obstacles.len() {
+            if obstacles[j].bottom() >= DINO_HEIGHT {
+                break;
+            }
+            let Some((up, down)) = arc.above(obstacles[j].top() + JUMP_MARGIN_HEIGHT) else {
+                break;
+            };
+            let (from, to) = touching(&obstacles[j]);
+            earliest_takeoff = earliest_takeoff.max(to - speed * down);
+            latest_takeoff = latest_takeoff.min(from - speed * up);
+            let takeoff = landing.max(earliest_takeoff);
+            if takeoff > latest_takeoff {
+                break;
+            }
+            let landed = takeoff + speed * arc.airtime() + margin;
+            // the next one has to be part of this jump if it's down too late for it
+            if down_before_next(landed, obstacles.get(j + 1)) {
+                keep_earliest(&mut earliest, j + 1, landed);
+            }
+        }
+    }
+    earliest[obstacles.len()]
+}
+
+// the size range opens up from the smallest obstacles; at least a pixel, gen_range can't take an empty range
+fn random_size(rng: &mut impl Rng, tuning: &ObstacleTuning, size: f32) -> Vec2 {
+    let range = |min: f32, max: f32| min..(min + (max - min) * size).max(min + 1.);
+    Vec2::new(
+        rng.gen_range(range(tuning.width_min, tuning.width_max)).floor(),
+        rng.gen_range(range(tuning.height_min, tuning.height_max)).floor(),
+    )
+}
+
+fn single(rng: &mut impl Rng, tuning: &ObstacleTuning, difficulty: &Difficulty) -> Vec<PatternObstacle> {
+    if rng.gen::<f32>() < tuning.flyer_chance_max * difficulty.flyers {
+        let bottom = tuning.flyer_heights[rng.gen_range(0..tuning.flyer_heights.len())];
+        return vec![PatternObstacle { x: 0., bottom, size: tuning.flyer_size }];
+    }
+    vec![PatternObstacle { x: 0., bottom: 0., size: random_size(rng, tuning, difficulty.size) }]
+}
+
+// narrow ones of the same height, close enough that they're one jump
+fn cluster(rng: &mut impl Rng, tuning: &Tuning, difficulty: &Difficulty, count: usize) -> Vec<PatternObstacle> {
+    let obstacles = &tuning.obstacles;
+    let height = random_size(rng, obstacles, difficulty.size).y;
+    let mut x = 0.;
+    (0..count)
+        .map(|_| {
+            let width = random_size(rng, obstacles, difficulty.size * 0.5).x;
+            let obstacle = PatternObstacle { x, bottom: 0., size: Vec2::new(width, height) };
+            x += width + rng.gen_range(0.0..=tuning.patterns.cluster_gap_max).floor();
+            obstacle
+        })
+        .collect()
+}
+
+// three going up, each far enough from the last to land and jump again at `speed`
+fn staircase(tuning: &Tuning, difficulty: &Difficulty, arc: &JumpArc, speed: f32) -> Vec<PatternObstacle> {
+    let obstacles = &tuning.obstacles;
+    let tallest = obstacles.height_min + (obstacles.height_max - obstacles.height_min) * difficulty.size;
+    let gap = (speed * arc.airtime() + DINO_WIDTH * 2.).ceil();
+    (0..3)
+        .map(|step| PatternObstacle {
+            x: step as f32 * (obstacles.width_min + gap),
+            bottom: 0.,
+            size: Vec2::new(obstacles.width_min, obstacles.height_min.lerp(tallest, step as f32 / 2.).floor()),
+        })
+        .collect()
+}
+
+fn pick_pattern(
+    rng: &mut impl Rng,
+    tuning: &Tuning,
+    difficulty: &Difficulty,
+    arc: &JumpArc,
+    speed: f32,
+) -> Vec<PatternObstacle> {
+    let patterns = &tuning.patterns;
+    if rng.gen::<f32>() >= patterns.cluster_chance_max * difficulty.patterns {
+        return single(rng, &tuning.obstacles, difficulty);
+    }
+    let weights = [patterns.double_weight, patterns.triple_weight, patterns.staircase_weight]
+        .into_iter()
+        .chain(patterns.authored.iter().map(|pattern| pattern.weight));
+    // every weight is 0
+    let Ok(weights) = WeightedIndex::new(weights) else {
+        return single(rng, &tuning.obstacles, difficulty);
+    };
+    match weights.sample(rng) {
+        0 => cluster(rng, tuning, difficulty, 2),
+        1 => cluster(rng, tuning, difficulty, 3),
+        2 => staircase(tuning, difficulty, arc, speed),
+        authored => patterns.authored[authored - 3]
+            .obstacles
+            .iter()
+            .map(|obstacle| PatternObstacle { x: obstacle.x, bottom: obstacle.bottom, size: obstacle.size })
+            .collect(),
+    }
+}
+
+fn place(pattern: &[PatternObstacle], start: f32) -> Vec<PlacedObstacle> {
+    let mut placed: Vec<PlacedObstacle> = pattern
+        .iter()
+        .map(|obstacle| PlacedObstacle {
+            kind: obstacle.kind(),
+            position: Vec2::new(
+                start + obstacle.x + obstacle.size.x / 2.,
+                PLAIN_HEIGHT + obstacle.bottom + obstacle.size.y / 2.,
+            ),
+            size: obstacle.size,
+        })
+        .collect();
+    placed.sort_by(|a, b| a.left().total_cmp(&b.left()));
+    placed
+}
+
+// lays out the run one pattern at a time, in screen x; update_obstacles scrolls it along with the obstacles
+#[derive(Resource, Default)]
+pub struct PatternSpawner {
+    // where the next pattern starts, before the random spacing
+    pub next_x: f32,
+    // the earliest the dino can take off after everything laid out so far
+    landing: f32,
+}
+impl PatternSpawner {
+    pub fn new(start_x: f32) -> Self {
+        PatternSpawner {
+            next_x: start_x,
+            landing: -DINO_WIDTH / 2.,
+        }
+    }
+
+    pub fn scroll(&mut self, scrolled: f32) {
+        self.next_x -= scrolled;
+        self.landing -= scrolled;
+    }
+
+    // the next pattern, checked at every speed in `speeds` (the speed now and the fastest it can get before the dino is there)
+    pub fn next(
+        &mut self,
+        rng: &mut impl Rng,
+        tuning: &Tuning,
+        difficulty: &Difficulty,
+        speeds: &[f32],
+    ) -> Vec<PlacedObstacle> {
+        let arc = JumpArc::new(&tuning.jump);
+        let fastest = speeds.iter().copied().fold(0., f32::max);
+        let spacing = (rng.gen_range(-tuning.obstacles.spacing_max..tuning.obstacles.spacing_max)
+            * (1. - difficulty.spacing))
+            .floor();
+        let start = self.next_x + spacing;
+        let mut pattern = pick_pattern(rng, tuning, difficulty, &arc, fastest);
+
+        loop {
+            for tries in 0..REPAIR_TRIES {
+                let placed = place(&pattern, start + tries as f32 * REPAIR_PUSH);
+                let landings: Option<Vec<f32>> =
+                    speeds.iter().map(|&speed| fit(&arc, speed, self.landing, &placed)).collect();
+                if let Some(landings) = landings {
+                    self.landing = landings.into_iter().fold(self.landing, f32::max);
+                    self.finish(&placed, tuning);
+                    return placed;
+                }
+            }
+            if pattern.len() > 1 {
+                pattern.pop();
+                continue;
+            }
+            // only a broken jump in the tuning file gets here
+            warn!("couldn't lay out an obstacle the dino can get over, placing it anyway");
+            let placed = place(&pattern, start + REPAIR_TRIES as f32 * REPAIR_PUSH);
+            self.finish(&placed, tuning);
+            return placed;
+        }
+    }
+
+    fn finish(&mut self, placed: &[PlacedObstacle], tuning: &Tuning) {
+        let end = placed.iter().map(PlacedObstacle::right).fold(f32::NEG_INFINITY, f32::max);
+        self.next_x = end + tuning.obstacles.spacing;
+    }
+}
+
+fn spawn_obstacle(commands: &mut Commands, obstacle: PlacedObstacle, palette: &Palette) {
+    commands.spawn((
+        Sprite::from_color(obstacle.kind.color(palette), obstacle.size),
+        Transform::from_translation(obstacle.position.extend(0.)),
+        Obstacle,
+        obstacle.kind,
+        obstacle.kind.hitbox(obstacle.size),
+        Position(obstacle.position),
+        PreviousPosition(obstacle.position),
+        Despawn,
+    ));
+}
+
+// everything up to the horizon is laid out, with a pickup in the gap after a pattern now and then
+fn fill_ahead(
+    commands: &mut Commands,
+    spawner: &mut PatternSpawner,
+    rng: &mut GameRng,
+    tuning: &Tuning,
+    difficulty: &Difficulty,
+    // patterns are laid out until the next one would start past this
+    horizon: f32,
+    palette: &Palette,
+) {
+    // the speed can only go up before the dino gets there, or down while slow motion is on
+    let speeds = [
+        tuning.speed.scroll_speed(difficulty.speed),
+        tuning.speed.scroll_speed(1.),
+        tuning.speed.scroll_speed(difficulty.speed * tuning.power_ups.slow_motion_scale),
+    ];
+    while spawner.next_x < horizon {
+        let placed = spawner.next(&mut **rng, tuning, difficulty, &speeds);
+        let end = placed.iter().map(PlacedObstacle::right).fold(f32::NEG_INFINITY, f32::max);
+        for obstacle in placed {
+            spawn_obstacle(commands, obstacle, palette);
+        }
+        if let Some((power_up, position)) = power_ups::roll_pickup(&mut rng.pickups, tuning, difficulty, end) {
+            power_ups::spawn_pickup(commands, power_up, position, tuning.power_ups.size, palette);
+        }
+    }
+}
+
+fn setup_patterns(
+    mut commands: Commands,
+    mut spawner: ResMut<PatternSpawner>,
+    mut game_rng: ResMut<GameRng>,
+    game_manager: Res<GameManager>,
+    tuning: Res<Tuning>,
+    mode: Res<GameMode>,
+    settings: Res<Settings>,
+) {
+    // a run-up before the first one, which is laid out right away
+    *spawner = PatternSpawner::new(game_manager.play_field.x - 100.);
+    let difficulty = tuning.difficulty.curves(*mode).sample(0.);
+    fill_ahead(
+        &mut commands,
+        &mut spawner,
+        &mut game_rng,
+        &tuning,
+        &difficulty,
+        game_manager.play_field.x,
+        &settings.file.palette(),
+    );
+}
+
+fn spawn_patterns(
+    mut commands: Commands,
+    mut spawner: ResMut<PatternSpawner>,
+    mut game_rng: ResMut<GameRng>,
+    game_manager: Res<GameManager>,
+    tuning: Res<Tuning>,
+    settings: Res<Settings>,
+) {
+    fill_ahead(
+        &mut commands,
+        &mut spawner,
+        &mut game_rng,
+        &tuning,
+        &game_manager.difficulty,
+        // just out of sight, see view.rs
+        view::reach(game_manager.play_field),
+        &settings.file.palette(),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    fn hardest() -> Difficulty {
+        Difficulty { speed: 1., spacing: 1., size: 1., flyers: 1., patterns: 1., power_ups: 1. }
+    }
+
+    fn run(seed: u64, tuning: &Tuning, difficulty: &Difficulty, speed: f32, patterns: usize) -> Vec<PlacedObstacle> {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let mut spawner = PatternSpawner::new(600.);
+        (0..patterns).flat_map(|_| spawner.next(&mut rng, tuning, difficulty, &[speed])).collect()
+    }
+
+    // a perfect player, stepped tick by tick like update_dino with jump held: tries every takeoff tick
+    fn clearable(obstacles: &[PlacedObstacle], speed: f32, jump: &JumpTuning) -> bool {
+        let delta = 1. / TICKS_PER_SECOND as f32;
+        let step = speed * delta;
+        let mut obstacles = obstacles.to_vec();
+        obstacles.sort_by(|a, b| a.left().total_cmp(&b.left()));
+        let widest = obstacles.iter().map(|obstacle| obstacle.size.x).fold(0., f32::max);
+
+        let hits = |tick: usize, y: f32| {
+            let scrolled = step * tick as f32;
+            let dino = Vec2::new(0., PLAIN_HEIGHT + y + DINO_HEIGHT / 2.);
+            let half = Vec2::new(DINO_WIDTH, DINO_HEIGHT) / 2.;
+            let first = obstacles.partition_point(|obstacle| obstacle.left() - scrolled < -half.x - widest);
+            obstacles[first..]
+                .iter()
+                .take_while(|obstacle| obstacle.left() - scrolled <= half.x)
+                .any(|obstacle| {
+                    let center = obstacle.position - Vec2::new(scrolled, 0.);
+                    let apart = (center - dino).abs() - (half + obstacle.size / 2.);
+                    apart.x <= 0. && apart.y <= 0.
+                })
+        };
+
+        let past = obstacles.iter().map(PlacedObstacle::right).fold(0., f32::max) + DINO_WIDTH;
+        let ticks = (past / step).ceil() as usize;
+        let mut grounded = vec![false; ticks + 1];
+        grounded[0] = true;
+        for tick in 0..ticks {
+            if !grounded[tick] {
+                continue;
+            }
+            if !hits(tick + 1, 0.) {
+                grounded[tick + 1] = true;
+            }
+            let (mut y, mut velocity) = (0., jump.jump_force);
+            for air_tick in tick + 1..=ticks {
+                velocity -= jump.gravity * delta;
+                y += velocity * delta;
+                if y < 0. {
+                    if !hits(air_tick, 0.) {
+                        grounded[air_tick] = true;
+                    }
+                    break;
+                }
+                if hits(air_tick, y) {
+                    break;
+                }
+                if air_tick == ticks {
+                    grounded[ticks] = true;
+                }
+            }
+        }
+        grounded[ticks]
+    }
+
+    #[test]
+    fn perfect_player_clears_everything() {
+        let mut tuning = Tuning::default();
+        // clusters every time, and one that can't be done in a single jump or two
+        tuning.patterns.cluster_chance_max = 1.;
+        tuning.patterns.authored.push(AuthoredPattern {
+            weight: 1.,
+            obstacles: vec![
+                AuthoredObstacle::ground(0., Vec2::new(60., 70.)),
+                AuthoredObstacle::ground(70., Vec2::new(60., 70.)),
+                AuthoredObstacle { x: 140., bottom: 75., size: Vec2::new(200., 20.) },
+            ],
+        });
+        let mut clusters = 0;
+        for seed in 0..4 {
+            for speed in [300., 400., 500.] {
+                for spacing in [0., 1.] {
+                    let difficulty = Difficulty { spacing, ..hardest() };
+                    let obstacles = run(seed, &tuning, &difficulty, speed, 40);
+                    clusters += obstacles.len() - 40;
+                    assert!(
+                        clearable(&obstacles, speed, &tuning.jump),
+                        "seed {seed} at {speed} with spacing {spacing}"
+                    );
+                }
+            }
+        }
+        // the check isn't just passing because everything came out as singles
+        assert!(clusters > 100);
+    }
+
+    #[test]
+    fn impossible_gaps_are_caught() {
+        let arc = JumpArc::new(&JumpTuning::default());
+        let obstacle = |x: f32| PlacedObstacle {
+            kind: ObstacleKind::Ground,
+            position: Vec2::new(x, PLAIN_HEIGHT + 35.),
+            size: Vec2::new(30., 70.),
+        };
+        assert!(fit(&arc, 300., 0., &[obstacle(300.)]).is_some());
+        // too close to jump twice, too far apart for one jump
+        let too_close = [obstacle(300.), obstacle(420.)];
+        assert!(fit(&arc, 300., 0., &too_close).is_none());
+        assert!(!clearable(&too_close, 300., &JumpTuning::default()));
+        assert!(fit(&arc, 300., 0., &[obstacle(300.), obstacle(700.)]).is_some());
+    }
+
+    #[test]
+    fn same_seed_same_patterns() {
+        let tuning = Tuning::default();
+        assert_eq!(run(1234, &tuning, &hardest(), 400., 100), run(1234, &tuning, &hardest(), 400., 100));
+        assert_ne!(run(1234, &tuning, &hardest(), 400., 100), run(4321, &tuning, &hardest(), 400., 100));
+    }
+}
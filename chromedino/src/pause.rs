@@ -0,0 +1,139 @@
+use bevy::prelude::*;
+use bevy::window::WindowFocused;
+
+use crate::actions::Action;
+use crate::rng::GameRng;
+use crate::{despawn_screen, spawn_button, ButtonSize, GameState, InRun, RunState};
+
+// the pause action (escape, p or start) during a run (or a replay), and whenever the window loses focus
+pub struct PausePlugin;
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut App) {
+        // headless there's no window plugin to add it
+        app.add_event::<WindowFocused>()
+            .add_systems(Update, (toggle_pause, pause_on_focus_loss).run_if(in_state(InRun)))
+            .add_systems(OnEnter(RunState::Paused), (freeze_time, setup_pause_overlay))
+            .add_systems(Update, pause_buttons.run_if(in_state(RunState::Paused)))
+            // also runs when the run is left from the overlay
+            .add_systems(OnExit(RunState::Paused), (unfreeze_time, despawn_pause_overlay))
+            .add_systems(
+                OnTransition {
+                    exited: GameState::Game,
+                    entered: GameState::Menu,
+                },
+                despawn_screen,
+            );
+    }
+}
+
+#[derive(Component)]
+struct PauseOverlay;
+
+#[derive(Component)]
+enum PauseButton {
+    Resume,
+    Restart,
+    MainMenu,
+}
+
+fn toggle_pause(
+    actions: Res<ButtonInput<Action>>,
+    run_state: Res<State<RunState>>,
+    mut next_run_state: ResMut<NextState<RunState>>,
+) {
+    if !actions.just_pressed(Action::Pause) {
+        return;
+    }
+    next_run_state.set(match run_state.get() {
+        RunState::Running => RunState::Paused,
+        RunState::Paused => RunState::Running,
+    });
+}
+
+fn pause_on_focus_loss(
+    mut focus_events: EventReader<WindowFocused>,
+    mut next_run_state: ResMut<NextState<RunState>>,
+) {
+    if focus_events.read().any(|event| !event.focused) {
+        next_run_state.set(RunState::Paused);
+    }
+}
+
+// virtual time stops, so the fixed ticks and every timer ticked with it stop right where they are
+fn freeze_time(mut time: ResMut<Time<Virtual>>) {
+    time.pause();
+}
+
+fn unfreeze_time(mut time: ResMut<Time<Virtual>>) {
+    time.unpause();
+}
+
+fn setup_pause_overlay(mut commands: Commands) {
+    commands
+    .spawn((Node {
+        width: Val::Percent(100.0),
+        height: Val::Percent(100.0),
+        position_type: PositionType::Absolute,
+        flex_direction: FlexDirection::Column,
+        align_items: AlignItems::Center,
+        justify_content: JustifyContent::Center,
+        row_gap: Val::Px(20.0),
+        ..default()
+    },
+    BackgroundColor(Color::srgba(0., 0., 0., 0.6)),
+    PauseOverlay))
+    .with_children(|parent| {
+        parent.spawn((
+            Text::new("Paused"),
+            TextColor(Color::srgb(0.9, 0.9, 0.9)),
+        ));
+        for (label, button) in [
+            ("Resume", PauseButton::Resume),
+            ("Restart", PauseButton::Restart),
+            ("Main Menu", PauseButton::MainMenu),
+        ] {
+            spawn_button(parent, ButtonSize::Large, Val::Px(160.0), Text::new(label), button);
+        }
+    });
+}
+
+fn pause_buttons(
+    mut commands: Commands,
+    interaction_query: Query<(&Interaction, &PauseButton), Changed<Interaction>>,
+    mut next_run_state: ResMut<NextState<RunState>>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    for (interaction, button) in &interaction_query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match button {
+            PauseButton::Resume => next_run_state.set(RunState::Running),
+            PauseButton::Restart => {
+                commands.run_system_cached(restart_run);
+                next_run_state.set(RunState::Running);
+            }
+            PauseButton::MainMenu => game_state.set(GameState::Menu),
+        }
+    }
+}
+
+// the state doesn't change, so the run is set up again by hand. on the same seed: a replay starts over from its
+// first tick on its own course, and a race stays on the ghost's
+fn restart_run(world: &mut World) {
+    if let Err(e) = world.run_system_cached(despawn_screen) {
+        warn!("couldn't clear the run: {e}");
+    }
+    let mut game_rng = world.resource_mut::<GameRng>();
+    game_rng.next_seed = Some(game_rng.seed);
+    world.run_schedule(OnEnter(InRun));
+}
+
+fn despawn_pause_overlay(
+    mut commands: Commands,
+    query: Query<Entity, With<PauseOverlay>>,
+) {
+    for entity in &query {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,272 @@
+use bevy::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::collision::{self, Hitbox, Shape};
+use crate::difficulty::Difficulty;
+use crate::settings::Palette;
+use crate::sound::Sound;
+use crate::tuning::Tuning;
+use crate::{Despawn, Dino, GameplaySet, InRun, Position, PreviousPosition, PLAIN_HEIGHT};
+
+// pickups come in the gaps between the patterns (see patterns.rs) and scroll and despawn with the obstacles;
+// running into one turns its effect on for a while. everything here is per tick, so replays still play out the same
+pub struct PowerUpPlugin;
+impl Plugin for PowerUpPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<PickedUp>()
+            .init_resource::<PowerUps>()
+            .add_systems(OnEnter(InRun), reset_power_ups)
+            .add_systems(
+                FixedUpdate,
+                (collect_pickups.after(collision::detect_collisions), tick_power_ups)
+                    .chain()
+                    .before(crate::advance_tick)
+                    .in_set(GameplaySet),
+            );
+    }
+}
+
+#[derive(Component, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum PowerUp {
+    // the next obstacle it runs into breaks instead
+    Shield,
+    // the game speed goes down for a bit
+    SlowMotion,
+    // one more jump in the air
+    DoubleJump,
+}
+impl PowerUp {
+    pub const ALL: [PowerUp; 3] = [PowerUp::Shield, PowerUp::SlowMotion, PowerUp::DoubleJump];
+
+    // for replay files
+    pub fn to_byte(self) -> u8 {
+        self as u8
+    }
+
+    pub fn from_byte(byte: u8) -> Option<PowerUp> {
+        PowerUp::ALL.get(byte as usize).copied()
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PowerUp::Shield => "shield",
+            PowerUp::SlowMotion => "slow motion",
+            PowerUp::DoubleJump => "double jump",
+        }
+    }
+
+    fn color(self, palette: &Palette) -> Color {
+        match self {
+            PowerUp::Shield => palette.shield,
+            PowerUp::SlowMotion => palette.slow_motion,
+            PowerUp::DoubleJump => palette.double_jump,
+        }
+    }
+
+    pub(crate) fn seconds(self, tuning: &PowerUpTuning) -> f32 {
+        match self {
+            PowerUp::Shield => tuning.shield_seconds,
+            PowerUp::SlowMotion => tuning.slow_motion_seconds,
+            PowerUp::DoubleJump => tuning.double_jump_seconds,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct PowerUpTuning {
+    // chance of a pickup after a pattern, at the top of the power_ups difficulty curve
+    pub chance_max: f32,
+    pub size: f32,
+    // bottom edge above the ground
+    pub heights: Vec<f32>,
+    pub shield_seconds: f32,
+    pub slow_motion_seconds: f32,
+    // the game speed is multiplied by this while it lasts
+    pub slow_motion_scale: f32,
+    pub double_jump_seconds: f32,
+}
+impl Default for PowerUpTuning {
+    fn default() -> Self {
+        PowerUpTuning {
+            chance_max: 0.2,
+            size: 20.,
+            heights: vec![5., 50.],
+            shield_seconds: 10.,
+            slow_motion_seconds: 5.,
+            slow_motion_scale: 0.5,
+            double_jump_seconds: 10.,
+        }
+    }
+}
+impl PowerUpTuning {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(0. ..=1.).contains(&self.chance_max) {
+            return Err("power_ups.chance_max has to be between 0 and 1".to_string());
+        }
+        if self.size <= 0. {
+            return Err("power_ups.size has to be above 0".to_string());
+        }
+        if self.heights.is_empty() {
+            return Err("power_ups.heights is empty".to_string());
+        }
+        if PowerUp::ALL.iter().any(|power_up| power_up.seconds(self) < 0.) {
+            return Err("power ups can't last a negative number of seconds".to_string());
+        }
+        if self.slow_motion_scale <= 0. || self.slow_motion_scale > 1. {
+            return Err("power_ups.slow_motion_scale has to be above 0 and at most 1".to_string());
+        }
+        Ok(())
+    }
+}
+
+// something to pick up, drawn as a plain square in its color
+#[derive(Component)]
+pub struct Pickup(pub PowerUp);
+
+// the dino ran into a pickup; replays keep these for the ghost
+#[derive(Event, Clone, Copy, Debug)]
+pub struct PickedUp(pub PowerUp);
+
+// seconds left of each effect that's on
+#[derive(Resource, Default, Debug)]
+pub struct PowerUps(BTreeMap<PowerUp, f32>);
+impl PowerUps {
+    // another of the same starts it over
+    pub fn grant(&mut self, power_up: PowerUp, seconds: f32) {
+        self.0.insert(power_up, seconds);
+    }
+
+    pub fn is_active(&self, power_up: PowerUp) -> bool {
+        self.0.contains_key(&power_up)
+    }
+
+    // turns it off; false if it wasn't on
+    pub fn take(&mut self, power_up: PowerUp) -> bool {
+        self.0.remove(&power_up).is_some()
+    }
+
+    pub fn active(&self) -> impl Iterator<Item = (PowerUp, f32)> + '_ {
+        self.0.iter().map(|(power_up, seconds)| (*power_up, *seconds))
+    }
+
+    pub(crate) fn tick(&mut self, delta: f32) {
+        self.0.retain(|_, seconds| {
+            *seconds -= delta;
+            *seconds > 0.
+        });
+    }
+
+    // the game speed while slow motion is on
+    pub fn speed_scale(&self, tuning: &PowerUpTuning) -> f32 {
+        if self.is_active(PowerUp::SlowMotion) {
+            tuning.slow_motion_scale
+        } else {
+            1.
+        }
+    }
+}
+
+// maybe a pickup in the gap after a pattern that ends at `end`
+pub(crate) fn roll_pickup(
+    rng: &mut impl Rng,
+    tuning: &Tuning,
+    difficulty: &Difficulty,
+    end: f32,
+) -> Option<(PowerUp, Vec2)> {
+    let power_ups = &tuning.power_ups;
+    if rng.gen::<f32>() >= power_ups.chance_max * difficulty.power_ups {
+        return None;
+    }
+    let power_up = PowerUp::ALL[rng.gen_range(0..PowerUp::ALL.len())];
+    let height = power_ups.heights[rng.gen_range(0..power_ups.heights.len())];
+    // the middle of the smallest the gap can get
+    let obstacles = &tuning.obstacles;
+    let x = end + (obstacles.spacing - obstacles.spacing_max) / 2.;
+    Some((power_up, Vec2::new(x, PLAIN_HEIGHT + height + power_ups.size / 2.)))
+}
+
+pub(crate) fn spawn_pickup(commands: &mut Commands, power_up: PowerUp, position: Vec2, size: f32, palette: &Palette) {
+    commands.spawn((
+        Sprite::from_color(power_up.color(palette), Vec2::splat(size)),
+        Transform::from_translation(position.extend(0.)),
+        Pickup(power_up),
+        Hitbox {
+            shapes: vec![Shape::Circle {
+                center: Vec2::ZERO,
+                radius: size / 2.,
+            }],
+        },
+        Position(position),
+        PreviousPosition(position),
+        Despawn,
+    ));
+}
+
+fn reset_power_ups(mut power_ups: ResMut<PowerUps>) {
+    *power_ups = PowerUps::default();
+}
+
+pub(crate) fn collect_pickups(
+    mut commands: Commands,
+    dino_query: Query<(&Position, &Hitbox), With<Dino>>,
+    pickup_query: Query<(Entity, &Position, &Hitbox, &Pickup)>,
+    mut power_ups: ResMut<PowerUps>,
+    tuning: Res<Tuning>,
+    mut sounds: EventWriter<Sound>,
+    mut picked_up: EventWriter<PickedUp>,
+) {
+    let Ok((dino_position, dino_hitbox)) = dino_query.get_single() else { return };
+    for (entity, position, hitbox, Pickup(power_up)) in &pickup_query {
+        if dino_hitbox.intersects(**dino_position, hitbox, **position) {
+            power_ups.grant(*power_up, power_up.seconds(&tuning.power_ups));
+            commands.entity(entity).despawn();
+            sounds.send(Sound::PowerUp);
+            picked_up.send(PickedUp(*power_up));
+        }
+    }
+}
+
+fn tick_power_ups(mut power_ups: ResMut<PowerUps>, time: Res<Time>) {
+    power_ups.tick(time.delta_secs());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn pickups_follow_the_difficulty() {
+        let tuning = Tuning::default();
+        let mut rng = ChaCha8Rng::seed_from_u64(7);
+        let none = Difficulty::default();
+        assert!((0..100).all(|_| roll_pickup(&mut rng, &tuning, &none, 0.).is_none()));
+
+        let hardest = Difficulty {
+            power_ups: 1.,
+            ..default()
+        };
+        let rolled: Vec<_> = (0..1000).filter_map(|_| roll_pickup(&mut rng, &tuning, &hardest, 100.)).collect();
+        let expected = 1000. * tuning.power_ups.chance_max;
+        assert!((rolled.len() as f32 - expected).abs() < expected * 0.3, "{}", rolled.len());
+        // in the gap, never on top of the pattern
+        assert!(rolled.iter().all(|(_, position)| position.x > 100.));
+    }
+
+    #[test]
+    fn effects_wear_off() {
+        let mut power_ups = PowerUps::default();
+        power_ups.grant(PowerUp::SlowMotion, 1.);
+        let tuning = PowerUpTuning::default();
+        assert_eq!(power_ups.speed_scale(&tuning), tuning.slow_motion_scale);
+        assert!(!power_ups.take(PowerUp::Shield));
+        power_ups.tick(0.5);
+        assert!(power_ups.is_active(PowerUp::SlowMotion));
+        power_ups.tick(0.5);
+        assert_eq!(power_ups.speed_scale(&tuning), 1.);
+    }
+}
@@ -0,0 +1,487 @@
+use bevy::prelude::*;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::difficulty::GameMode;
+use crate::autoplay;
+use crate::power_ups::{self, PickedUp, PowerUp};
+use crate::rng::{self, GameRng};
+use crate::score::{self, SaveData};
+use crate::{despawn_screen, spawn_button, ButtonSize, Despawn, DinoInput, DinoInputSet, GameState, GameplaySet, InRun, RunTick};
+
+// file layout (all numbers are LEB128 varints unless noted):
+// "DINO", version (u8), seed (u64 le), tick count, then three tick lists (jump presses, duck toggles, jump toggles),
+// each one a count followed by the ticks, every tick relative to the previous one, the game mode (u8), and last the
+// pickups: a count, then a tick (relative like the others) and a power up (u8) each
+const REPLAY_MAGIC: &[u8; 4] = b"DINO";
+const REPLAY_VERSION: u8 = 1;
+const LAST_REPLAY_FILE: &str = "last.replay";
+pub(crate) const BEST_REPLAY_FILE: &str = "best.replay";
+const REPLAY_ARG: &str = "--replay";
+
+pub struct ReplayPlugin {
+    pub save_to_disk: bool,
+}
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ReplayDesync>()
+            .insert_resource(ReplaySource::from_args())
+            .insert_resource(Recording {
+                save_to_disk: self.save_to_disk,
+                replay: Replay::default(),
+            })
+            // recording
+            // InRun is entered after Game, and the seed is only rolled there
+            .add_systems(OnEnter(InRun), start_recording.after(rng::reseed).run_if(in_state(GameState::Game)))
+            .add_systems(FixedUpdate, record_tick.in_set(DinoInputSet).after(autoplay::drive_dino).run_if(in_state(GameState::Game)))
+            .add_systems(
+                FixedUpdate,
+                record_pickups
+                    .in_set(GameplaySet)
+                    .after(power_ups::collect_pickups)
+                    .before(crate::advance_tick)
+                    .run_if(in_state(GameState::Game)),
+            )
+            .add_systems(
+                OnTransition {
+                    exited: GameState::Game,
+                    entered: GameState::Dead,
+                },
+                save_recording.after(score::record_run),
+            )
+            // playback
+            .add_systems(OnEnter(GameState::Menu), setup_replay_button)
+            .add_systems(Update, replay_button.run_if(in_state(GameState::Menu)))
+            .add_systems(FixedUpdate, (play_input.in_set(DinoInputSet), check_replay_end.after(GameplaySet)).run_if(in_state(GameState::Replay)))
+            .add_systems(
+                OnTransition {
+                    exited: GameState::Replay,
+                    entered: GameState::Menu,
+                },
+                despawn_screen,
+            )
+            // the state is already the next one here, and the playback has to be checked before it goes
+            .add_systems(
+                OnExit(GameState::Replay),
+                (check_replay_death.run_if(in_state(GameState::Dead)), stop_playback).chain(),
+            );
+    }
+}
+
+// a whole run: the seed, how many ticks it lasted and the ticks the inputs happened on
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Replay {
+    pub seed: u64,
+    pub ticks: u32,
+    pub jumps: Vec<u32>,
+    // a key is held from the first toggle to the second, from the third to the fourth...
+    pub duck_toggles: Vec<u32>,
+    pub jump_toggles: Vec<u32>,
+    pub mode: GameMode,
+    // the power ups it ran into, for the ghost; the player's world has the pickups themselves
+    pub pickups: Vec<(u32, PowerUp)>,
+}
+
+// the replay didn't play out like the run it recorded, sent with the warning
+#[derive(Event, Debug)]
+pub struct ReplayDesync {
+    pub tick: u32,
+}
+
+#[derive(Debug)]
+pub enum ReplayError {
+    Io(std::io::Error),
+    NotAReplay,
+    UnsupportedVersion(u8),
+    UnknownMode(u8),
+    UnknownPowerUp(u8),
+    Truncated,
+}
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReplayError::Io(e) => write!(f, "{e}"),
+            ReplayError::NotAReplay => write!(f, "not a replay file"),
+            ReplayError::UnsupportedVersion(version) => write!(f, "unsupported replay version {version}"),
+            ReplayError::UnknownMode(mode) => write!(f, "unknown game mode {mode}"),
+            ReplayError::UnknownPowerUp(power_up) => write!(f, "unknown power up {power_up}"),
+            ReplayError::Truncated => write!(f, "replay file is truncated"),
+        }
+    }
+}
+impl From<std::io::Error> for ReplayError {
+    fn from(e: std::io::Error) -> Self {
+        ReplayError::Io(e)
+    }
+}
+
+impl Replay {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = REPLAY_MAGIC.to_vec();
+        bytes.push(REPLAY_VERSION);
+        bytes.extend_from_slice(&self.seed.to_le_bytes());
+        write_varint(&mut bytes, self.ticks as u64);
+        write_ticks(&mut bytes, &self.jumps);
+        write_ticks(&mut bytes, &self.duck_toggles);
+        write_ticks(&mut bytes, &self.jump_toggles);
+        bytes.push(self.mode.to_byte());
+        write_ticks(&mut bytes, &self.pickups.iter().map(|(tick, _)| *tick).collect::<Vec<_>>());
+        bytes.extend(self.pickups.iter().map(|(_, power_up)| power_up.to_byte()));
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Replay, ReplayError> {
+        let rest = bytes.strip_prefix(REPLAY_MAGIC).ok_or(ReplayError::NotAReplay)?;
+        let (&version, mut rest) = rest.split_first().ok_or(ReplayError::Truncated)?;
+        if version != REPLAY_VERSION {
+            return Err(ReplayError::UnsupportedVersion(version));
+        }
+        let seed = rest.get(..8).ok_or(ReplayError::Truncated)?;
+        let seed = u64::from_le_bytes(seed.try_into().unwrap());
+        rest = &rest[8..];
+
+        let ticks = read_varint(&mut rest)? as u32;
+        let jumps = read_ticks(&mut rest)?;
+        let duck_toggles = read_ticks(&mut rest)?;
+        let jump_toggles = read_ticks(&mut rest)?;
+        let (&byte, mut rest) = rest.split_first().ok_or(ReplayError::Truncated)?;
+        let mode = GameMode::from_byte(byte).ok_or(ReplayError::UnknownMode(byte))?;
+        let pickup_ticks = read_ticks(&mut rest)?;
+        let power_ups = rest.get(..pickup_ticks.len()).ok_or(ReplayError::Truncated)?;
+        let pickups = pickup_ticks
+            .into_iter()
+            .zip(power_ups)
+            .map(|(tick, &byte)| Ok((tick, PowerUp::from_byte(byte).ok_or(ReplayError::UnknownPowerUp(byte))?)))
+            .collect::<Result<_, ReplayError>>()?;
+
+        Ok(Replay { seed, ticks, jumps, duck_toggles, jump_toggles, mode, pickups })
+    }
+
+    // what the dino was told on that tick
+    pub fn input(&self, tick: u32) -> DinoInput {
+        DinoInput {
+            jump: self.jumps.binary_search(&tick).is_ok(),
+            jump_held: self.jump_held(tick),
+            duck: self.duck_held(tick),
+        }
+    }
+
+    pub fn duck_held(&self, tick: u32) -> bool {
+        held(&self.duck_toggles, tick)
+    }
+
+    pub fn jump_held(&self, tick: u32) -> bool {
+        held(&self.jump_toggles, tick)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), ReplayError> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, self.to_bytes())?;
+        Ok(())
+    }
+
+    pub fn load(path: &Path) -> Result<Replay, ReplayError> {
+        Replay::from_bytes(&fs::read(path)?)
+    }
+}
+
+fn write_varint(bytes: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        bytes.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+fn held(toggles: &[u32], tick: u32) -> bool {
+    toggles.partition_point(|&toggle| toggle <= tick) % 2 == 1
+}
+
+fn record_toggle(toggles: &mut Vec<u32>, held: bool, tick: u32) {
+    if held != (toggles.len() % 2 == 1) {
+        toggles.push(tick);
+    }
+}
+
+fn write_ticks(bytes: &mut Vec<u8>, ticks: &[u32]) {
+    write_varint(bytes, ticks.len() as u64);
+    let mut last = 0;
+    for &tick in ticks {
+        write_varint(bytes, (tick - last) as u64);
+        last = tick;
+    }
+}
+
+fn read_ticks(bytes: &mut &[u8]) -> Result<Vec<u32>, ReplayError> {
+    let count = read_varint(bytes)?;
+    let mut last = 0;
+    (0..count)
+        .map(|_| {
+            last += read_varint(bytes)? as u32;
+            Ok(last)
+        })
+        .collect()
+}
+
+fn read_varint(bytes: &mut &[u8]) -> Result<u64, ReplayError> {
+    let mut value = 0;
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = bytes.split_first().ok_or(ReplayError::Truncated)?;
+        *bytes = rest;
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(ReplayError::Truncated)
+}
+
+// the run being played right now
+#[derive(Resource, Deref, DerefMut)]
+pub struct Recording {
+    #[deref]
+    replay: Replay,
+    save_to_disk: bool,
+}
+
+// the run being watched
+#[derive(Resource)]
+struct Playback(Replay);
+
+// the replay plays in its own mode, the player's comes back after
+#[derive(Resource)]
+struct ModeBeforePlayback(GameMode);
+
+// which file the menu's replay button plays: --replay <path>, or the last run
+#[derive(Resource)]
+pub struct ReplaySource(pub Option<PathBuf>);
+impl ReplaySource {
+    fn from_args() -> Self {
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            if arg == REPLAY_ARG {
+                return ReplaySource(args.next().map(PathBuf::from));
+            }
+        }
+        ReplaySource(score::data_path(LAST_REPLAY_FILE))
+    }
+}
+
+#[derive(Component)]
+struct ReplayButton;
+
+fn start_recording(mut recording: ResMut<Recording>, game_rng: Res<GameRng>, mode: Res<GameMode>) {
+    **recording = Replay {
+        seed: game_rng.seed,
+        mode: *mode,
+        ..default()
+    };
+}
+
+fn record_tick(
+    mut recording: ResMut<Recording>,
+    input: Res<DinoInput>,
+    run_tick: Res<RunTick>,
+) {
+    if input.jump {
+        recording.jumps.push(**run_tick);
+    }
+    record_toggle(&mut recording.duck_toggles, input.duck, **run_tick);
+    record_toggle(&mut recording.jump_toggles, input.jump_held, **run_tick);
+    // the dino dies on the last recorded tick
+    recording.ticks = **run_tick + 1;
+}
+
+fn record_pickups(mut recording: ResMut<Recording>, mut picked_up: EventReader<PickedUp>, run_tick: Res<RunTick>) {
+    for PickedUp(power_up) in picked_up.read() {
+        recording.pickups.push((**run_tick, *power_up));
+    }
+}
+
+pub(crate) fn save_recording(recording: Res<Recording>, save: Res<SaveData>) {
+    if !recording.save_to_disk {
+        return;
+    }
+    let mut files = vec![LAST_REPLAY_FILE];
+    // the day's best isn't the best run
+    if save.last_run_was_best && save.daily.is_none() {
+        files.push(BEST_REPLAY_FILE);
+    }
+    for path in files.into_iter().filter_map(score::data_path) {
+        if let Err(e) = recording.save(&path) {
+            warn!("couldn't save replay {}: {e}", path.display());
+        }
+    }
+}
+
+fn setup_replay_button(
+    mut commands: Commands,
+    source: Res<ReplaySource>,
+) {
+    if !source.0.as_ref().is_some_and(|path| path.exists()) {
+        return;
+    }
+    commands
+    .spawn((Node {
+        width: Val::Percent(100.0),
+        position_type: PositionType::Absolute,
+        bottom: Val::Px(40.0),
+        justify_content: JustifyContent::Center,
+        ..default()
+    }, Despawn))
+    .with_children(|parent| {
+        spawn_button(parent, ButtonSize::Large, Val::Auto, Text::new("Watch replay"), ReplayButton);
+    });
+}
+
+fn replay_button(
+    mut commands: Commands,
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<ReplayButton>)>,
+    source: Res<ReplaySource>,
+    mut game_rng: ResMut<GameRng>,
+    mut mode: ResMut<GameMode>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    if !interaction_query.iter().any(|interaction| *interaction == Interaction::Pressed) {
+        return;
+    }
+    let Some(path) = &source.0 else { return };
+    let replay = match Replay::load(path) {
+        Ok(replay) => replay,
+        Err(e) => {
+            warn!("couldn't load replay {}: {e}", path.display());
+            return;
+        }
+    };
+
+    game_rng.next_seed = Some(replay.seed);
+    commands.insert_resource(ModeBeforePlayback(*mode));
+    *mode = replay.mode;
+    commands.insert_resource(Playback(replay));
+    game_state.set(GameState::Replay);
+}
+
+fn play_input(
+    mut input: ResMut<DinoInput>,
+    playback: Res<Playback>,
+    run_tick: Res<RunTick>,
+) {
+    *input = playback.0.input(**run_tick);
+}
+
+// the dino should have died by now
+fn check_replay_end(
+    playback: Res<Playback>,
+    run_tick: Res<RunTick>,
+    mut game_state: ResMut<NextState<GameState>>,
+    mut desyncs: EventWriter<ReplayDesync>,
+) {
+    if **run_tick >= playback.0.ticks && !matches!(*game_state, NextState::Pending(_)) {
+        warn!("replay desynced: the recording ended but the dino is still alive");
+        desyncs.send(ReplayDesync { tick: **run_tick });
+        game_state.set(GameState::Dead);
+    }
+}
+
+fn check_replay_death(playback: Res<Playback>, run_tick: Res<RunTick>, mut desyncs: EventWriter<ReplayDesync>) {
+    if **run_tick != playback.0.ticks {
+        warn!(
+            "replay desynced: died on tick {} but the recording died on tick {}",
+            **run_tick,
+            playback.0.ticks
+        );
+        desyncs.send(ReplayDesync { tick: **run_tick });
+    }
+}
+
+fn stop_playback(
+    mut commands: Commands,
+    mut game_rng: ResMut<GameRng>,
+    mode_before: Res<ModeBeforePlayback>,
+    mut mode: ResMut<GameMode>,
+) {
+    commands.remove_resource::<Playback>();
+    commands.remove_resource::<ModeBeforePlayback>();
+    game_rng.next_seed = None;
+    *mode = mode_before.0;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn replay() -> Replay {
+        Replay {
+            seed: 0xdead_beef_1234,
+            ticks: 500,
+            jumps: vec![3, 40, 41, 300, 499],
+            duck_toggles: vec![10, 20, 21, 400],
+            jump_toggles: vec![3, 10, 40, 45, 300, 310, 499],
+            mode: GameMode::Hardcore,
+            pickups: vec![(50, PowerUp::SlowMotion), (50, PowerUp::Shield), (420, PowerUp::DoubleJump)],
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let replay = replay();
+        assert_eq!(Replay::from_bytes(&replay.to_bytes()).unwrap(), replay);
+    }
+
+    #[test]
+    fn file_round_trip() {
+        let path = std::env::temp_dir()
+            .join(format!("chromedino-test-{}", std::process::id()))
+            .join("round_trip.replay");
+        let replay = replay();
+        replay.save(&path).unwrap();
+        assert_eq!(Replay::load(&path).unwrap(), replay);
+    }
+
+    #[test]
+    fn rejects_bad_files() {
+        let bytes = replay().to_bytes();
+        assert!(matches!(Replay::from_bytes(b"PNG!"), Err(ReplayError::NotAReplay)));
+        assert!(matches!(
+            Replay::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(ReplayError::Truncated)
+        ));
+
+        // the mode byte sits right before the pickups
+        let mode_at = Replay { pickups: Vec::new(), ..replay() }.to_bytes().len() - 2;
+        let mut unknown_mode = bytes.clone();
+        unknown_mode[mode_at] = 200;
+        assert!(matches!(
+            Replay::from_bytes(&unknown_mode),
+            Err(ReplayError::UnknownMode(200))
+        ));
+
+        let mut unknown_power_up = bytes.clone();
+        *unknown_power_up.last_mut().unwrap() = 200;
+        assert!(matches!(
+            Replay::from_bytes(&unknown_power_up),
+            Err(ReplayError::UnknownPowerUp(200))
+        ));
+
+        let mut newer = bytes.clone();
+        newer[4] = REPLAY_VERSION + 1;
+        assert!(matches!(
+            Replay::from_bytes(&newer),
+            Err(ReplayError::UnsupportedVersion(_))
+        ));
+    }
+
+    #[test]
+    fn duck_is_held_between_toggles() {
+        let replay = replay();
+        assert!(!replay.duck_held(9));
+        assert!(replay.duck_held(10));
+        assert!(replay.duck_held(19));
+        assert!(!replay.duck_held(20));
+        assert!(replay.duck_held(399));
+        assert!(!replay.duck_held(1000));
+    }
+}
@@ -0,0 +1,71 @@
+use bevy::prelude::*;
+use rand::{thread_rng, Rng, SeedableRng};
+// chacha instead of StdRng: StdRng is allowed to change between rand versions, which would break old seeds
+use rand_chacha::ChaCha8Rng;
+
+const SEED_ARG: &str = "--seed";
+const SEED_ENV: &str = "CHROMEDINO_SEED";
+// the obstacles use stream 0
+const PICKUP_STREAM: u64 = 1;
+
+pub struct RngPlugin;
+impl Plugin for RngPlugin {
+    fn build(&self, app: &mut App) {
+        let fixed_seed = seed_from_args().or_else(seed_from_env);
+        if let Some(seed) = fixed_seed {
+            info!("using seed {seed}");
+        }
+        app.insert_resource(GameRng::new(fixed_seed));
+    }
+}
+
+// every random thing in a run has to come from here, otherwise seeds can't reproduce it
+#[derive(Resource, Deref, DerefMut)]
+pub struct GameRng {
+    #[deref]
+    rng: ChaCha8Rng,
+    pub seed: u64,
+    // the pickups roll from their own stream of the same seed, so they don't move the obstacles around
+    pub pickups: ChaCha8Rng,
+    // when set every run uses it, otherwise each run rolls a new one
+    pub fixed_seed: Option<u64>,
+    // used by the next run only, before fixed_seed (replays set this)
+    pub next_seed: Option<u64>,
+}
+impl GameRng {
+    pub fn new(fixed_seed: Option<u64>) -> Self {
+        let seed = fixed_seed.unwrap_or_else(|| thread_rng().gen());
+        let mut pickups = ChaCha8Rng::seed_from_u64(seed);
+        pickups.set_stream(PICKUP_STREAM);
+        GameRng {
+            rng: ChaCha8Rng::seed_from_u64(seed),
+            seed,
+            pickups,
+            fixed_seed,
+            next_seed: None,
+        }
+    }
+}
+
+// run on entering the game, before anything draws from the rng
+pub fn reseed(mut game_rng: ResMut<GameRng>) {
+    let fixed_seed = game_rng.fixed_seed;
+    *game_rng = GameRng::new(game_rng.next_seed.or(fixed_seed));
+    game_rng.fixed_seed = fixed_seed;
+}
+
+fn seed_from_args() -> Option<u64> {
+    crate::arg_value(SEED_ARG).and_then(|value| parse_seed(&value))
+}
+
+fn seed_from_env() -> Option<u64> {
+    std::env::var(SEED_ENV).ok().and_then(|value| parse_seed(&value))
+}
+
+fn parse_seed(value: &str) -> Option<u64> {
+    let seed = value.trim().parse().ok();
+    if seed.is_none() {
+        warn!("invalid seed {value:?}, using a random one");
+    }
+    seed
+}
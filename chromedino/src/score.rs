@@ -0,0 +1,473 @@
+use bevy::prelude::*;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::collision::{self, Collision};
+use crate::difficulty::GameMode;
+use crate::{GameState, GameplaySet, InRun};
+
+// bump this when the layout of SaveFile changes, and handle the old one in migrate()
+const SAVE_VERSION: u32 = 3;
+const SAVE_FILE_NAME: &str = "save.ron";
+const RUN_HISTORY_LENGTH: usize = 10;
+// a year of daily challenges
+const DAILY_HISTORY_LENGTH: usize = 366;
+const LEADERBOARD_LENGTH: usize = 10;
+// the profile a fresh save starts with, and the name of the runs from before there were any
+pub const DEFAULT_PROFILE: &str = "PLAYER";
+// the chrome dino gives roughly 10 points a second at the start
+pub(crate) const SCORE_PER_PIXEL: f32 = 0.025;
+
+pub struct ScorePlugin {
+    pub save_to_disk: bool,
+}
+impl Plugin for ScorePlugin {
+    fn build(&self, app: &mut App) {
+        let save = if self.save_to_disk {
+            SaveData::load_default()
+        } else {
+            SaveData::default()
+        };
+        app.insert_resource(save)
+            .init_resource::<RunScore>()
+            .add_systems(OnEnter(InRun), reset_run_score)
+            .add_systems(FixedUpdate, (tick_run_time, count_cleared.after(collision::detect_collisions)).in_set(GameplaySet))
+            // runs before OnEnter(Dead), so the death screen already sees the new best;
+            // daily challenges go in their own history (daily.rs)
+            .add_systems(
+                OnTransition {
+                    exited: GameState::Game,
+                    entered: GameState::Dead,
+                },
+                record_run.run_if(not(resource_equals(GameMode::Daily))),
+            );
+    }
+}
+
+// progress of the current run; distance is fed by update_obstacles
+#[derive(Resource, Default)]
+pub struct RunScore {
+    pub distance: f32,
+    pub seconds: f32,
+    // obstacles the dino got over
+    pub cleared: u32,
+}
+impl RunScore {
+    pub fn score(&self) -> u32 {
+        (self.distance * SCORE_PER_PIXEL) as u32
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RunRecord {
+    pub score: u32,
+    pub seconds: f32,
+    // unix time in seconds
+    pub date: u64,
+    // the profile that played it; older files don't have these
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub mode: GameMode,
+}
+
+// the best daily challenge run of a day
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DailyRecord {
+    pub score: u32,
+    pub seconds: f32,
+    pub attempts: u32,
+}
+
+// every field has a default so older (or partially broken) files still load
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct SaveFile {
+    pub version: u32,
+    pub best_score: u32,
+    // newest first
+    pub runs: Vec<RunRecord>,
+    // by the local date the challenge was for
+    pub daily: BTreeMap<NaiveDate, DailyRecord>,
+    // the best runs of every profile, best first
+    pub leaderboard: Vec<RunRecord>,
+    pub profiles: Vec<String>,
+    // the one playing, one of profiles
+    pub profile: String,
+}
+impl Default for SaveFile {
+    fn default() -> Self {
+        SaveFile {
+            version: SAVE_VERSION,
+            best_score: 0,
+            runs: Vec::new(),
+            daily: BTreeMap::new(),
+            leaderboard: Vec::new(),
+            profiles: vec![DEFAULT_PROFILE.to_string()],
+            profile: DEFAULT_PROFILE.to_string(),
+        }
+    }
+}
+
+impl SaveFile {
+    // never fails: a missing file is a fresh save, a broken one gets moved aside
+    pub fn load(path: &Path) -> SaveFile {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return SaveFile::default(),
+            Err(e) => {
+                warn!("couldn't read save file {}: {e}", path.display());
+                return SaveFile::default();
+            }
+        };
+        match ron::from_str::<SaveFile>(&text) {
+            Ok(save) if save.version > SAVE_VERSION => {
+                // written by a newer build; keep a copy since saving will drop what we don't know
+                warn!("save file version {} is newer than {SAVE_VERSION}", save.version);
+                backup(path, &format!("v{}", save.version));
+                save.migrate()
+            }
+            Ok(save) => save.migrate(),
+            Err(e) => {
+                warn!("save file {} is corrupt: {e}", path.display());
+                backup(path, "corrupt");
+                SaveFile::default()
+            }
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        let text = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(std::io::Error::other)?;
+        write_file(path, &text)
+    }
+
+    fn migrate(mut self) -> SaveFile {
+        // version 2 added daily, serde defaults cover it. version 3 added profiles and the leaderboard:
+        // the runs from before were all the one player's
+        if self.version < 3 {
+            for run in &mut self.runs {
+                run.name = DEFAULT_PROFILE.to_string();
+            }
+            for run in self.runs.clone() {
+                self.enter_leaderboard(run);
+            }
+        }
+        if !self.profiles.contains(&self.profile) {
+            self.switch_profile(&self.profile.clone());
+        }
+        self.version = SAVE_VERSION;
+        self.best_score = self
+            .runs
+            .iter()
+            .map(|run| run.score)
+            .fold(self.best_score, u32::max);
+        self.runs.truncate(RUN_HISTORY_LENGTH);
+        self.leaderboard.truncate(LEADERBOARD_LENGTH);
+        self.trim_daily();
+        self
+    }
+
+    // returns true on a new best
+    pub fn record(&mut self, run: RunRecord) -> bool {
+        let new_best = run.score > self.best_score;
+        if new_best {
+            self.best_score = run.score;
+        }
+        self.runs.insert(0, run);
+        self.runs.truncate(RUN_HISTORY_LENGTH);
+        new_best
+    }
+
+    // the place it got on the leaderboard, from 0, if it made it there
+    pub fn enter_leaderboard(&mut self, run: RunRecord) -> Option<usize> {
+        if run.score == 0 {
+            return None;
+        }
+        // after the runs with the same score, they got there first
+        let place = self.leaderboard.partition_point(|entry| entry.score >= run.score);
+        if place >= LEADERBOARD_LENGTH {
+            return None;
+        }
+        self.leaderboard.insert(place, run);
+        self.leaderboard.truncate(LEADERBOARD_LENGTH);
+        Some(place)
+    }
+
+    // makes it if it's new; an empty name is the default one
+    pub fn switch_profile(&mut self, name: &str) {
+        let name = if name.is_empty() { DEFAULT_PROFILE } else { name };
+        if !self.profiles.iter().any(|profile| profile == name) {
+            self.profiles.push(name.to_string());
+        }
+        self.profile = name.to_string();
+    }
+
+    pub fn next_profile(&mut self) {
+        let index = self.profiles.iter().position(|profile| *profile == self.profile).unwrap_or_default();
+        if let Some(next) = self.profiles.get((index + 1) % self.profiles.len().max(1)) {
+            self.profile = next.clone();
+        }
+    }
+
+    // a daily challenge run; returns true on a new best for that day
+    pub fn record_daily(&mut self, date: NaiveDate, score: u32, seconds: f32) -> bool {
+        let day = self.daily.entry(date).or_insert(DailyRecord {
+            score: 0,
+            seconds: 0.,
+            attempts: 0,
+        });
+        day.attempts += 1;
+        let new_best = day.attempts == 1 || score > day.score;
+        if new_best {
+            day.score = score;
+            day.seconds = seconds;
+        }
+        self.trim_daily();
+        new_best
+    }
+
+    // the oldest days go first
+    fn trim_daily(&mut self) {
+        while self.daily.len() > DAILY_HISTORY_LENGTH {
+            self.daily.pop_first();
+        }
+    }
+}
+
+// write next to it and swap, so a crash mid-write can't eat the old file
+pub(crate) fn write_file(path: &Path, text: &str) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, text)?;
+    fs::rename(&tmp, path)
+}
+
+pub(crate) fn backup(path: &Path, suffix: &str) {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{suffix}"));
+    if let Err(e) = fs::rename(path, &name) {
+        warn!("couldn't back up save file: {e}");
+    }
+}
+
+// the loaded save and where it goes; no path means nothing gets written to disk
+#[derive(Resource, Default)]
+pub struct SaveData {
+    pub path: Option<PathBuf>,
+    pub file: SaveFile,
+    pub last_run_was_best: bool,
+    // where the last run got on the leaderboard, from 0
+    pub last_place: Option<usize>,
+    // the day of the daily challenge being played, set by daily.rs
+    pub daily: Option<NaiveDate>,
+}
+impl SaveData {
+    // the best to beat: that day's in a daily challenge
+    pub fn best(&self) -> u32 {
+        match self.daily {
+            Some(date) => self.file.daily.get(&date).map_or(0, |day| day.score),
+            None => self.file.best_score,
+        }
+    }
+
+    fn load_default() -> SaveData {
+        let path = data_path(SAVE_FILE_NAME);
+        if path.is_none() {
+            warn!("no data directory, scores won't be saved");
+        }
+        SaveData {
+            file: path.as_deref().map(SaveFile::load).unwrap_or_default(),
+            path,
+            last_run_was_best: false,
+            last_place: None,
+            daily: None,
+        }
+    }
+
+    pub fn write(&self) {
+        if let Some(path) = &self.path {
+            if let Err(e) = self.file.save(path) {
+                warn!("couldn't write save file {}: {e}", path.display());
+            }
+        }
+    }
+}
+
+// where everything the game keeps between runs lives
+pub fn data_path(file_name: &str) -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("chromedino").join(file_name))
+}
+
+fn reset_run_score(mut run_score: ResMut<RunScore>, mut save: ResMut<SaveData>) {
+    *run_score = RunScore::default();
+    save.last_run_was_best = false;
+    save.last_place = None;
+}
+
+fn tick_run_time(mut run_score: ResMut<RunScore>, time: Res<Time>) {
+    run_score.seconds += time.delta_secs();
+}
+
+fn count_cleared(mut collisions: EventReader<Collision>, mut run_score: ResMut<RunScore>) {
+    for collision in collisions.read() {
+        if let Collision::Cleared { .. } = collision {
+            run_score.cleared += 1;
+        }
+    }
+}
+
+pub fn record_run(run_score: Res<RunScore>, mode: Res<GameMode>, mut save: ResMut<SaveData>) {
+    let date = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    let run = RunRecord {
+        score: run_score.score(),
+        seconds: run_score.seconds,
+        date,
+        name: save.file.profile.clone(),
+        mode: *mode,
+    };
+    save.last_place = save.file.enter_leaderboard(run.clone());
+    save.last_run_was_best = save.file.record(run);
+    save.write();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a fresh file path per test, tests run in parallel
+    fn temp_path(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("chromedino-test-{}-{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir.join(SAVE_FILE_NAME)
+    }
+
+    fn run(score: u32) -> RunRecord {
+        RunRecord {
+            score,
+            seconds: score as f32 / 10.,
+            date: 1_700_000_000 + score as u64,
+            name: DEFAULT_PROFILE.to_string(),
+            mode: GameMode::Classic,
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let path = temp_path("round_trip");
+        let mut save = SaveFile::default();
+        assert!(save.record(run(120)));
+        assert!(!save.record(run(80)));
+        save.save(&path).unwrap();
+
+        let loaded = SaveFile::load(&path);
+        assert_eq!(loaded, save);
+        assert_eq!(loaded.best_score, 120);
+        assert_eq!(loaded.runs[0].score, 80);
+    }
+
+    #[test]
+    fn missing_file_is_empty() {
+        let path = temp_path("missing");
+        assert_eq!(SaveFile::load(&path), SaveFile::default());
+    }
+
+    #[test]
+    fn corrupt_file_is_backed_up() {
+        let path = temp_path("corrupt");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, "(version: 1, best_score: \"lots\"").unwrap();
+
+        assert_eq!(SaveFile::load(&path), SaveFile::default());
+        assert!(!path.exists());
+        assert!(path.with_extension("ron.corrupt").exists());
+    }
+
+    #[test]
+    fn old_version_is_migrated() {
+        let path = temp_path("old_version");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, "(version: 0, runs: [(score: 42, seconds: 4.2, date: 0)])").unwrap();
+
+        let loaded = SaveFile::load(&path);
+        assert_eq!(loaded.version, SAVE_VERSION);
+        assert_eq!(loaded.best_score, 42);
+        assert_eq!(loaded.runs.len(), 1);
+        // the runs from before profiles are the default one's, and go on the leaderboard
+        assert_eq!(loaded.leaderboard, vec![RunRecord { score: 42, seconds: 4.2, date: 0, ..run(0) }]);
+        assert_eq!(loaded.profile, DEFAULT_PROFILE);
+    }
+
+    #[test]
+    fn history_is_capped() {
+        let mut save = SaveFile::default();
+        for score in 0..RUN_HISTORY_LENGTH as u32 * 2 {
+            save.record(run(score));
+        }
+        assert_eq!(save.runs.len(), RUN_HISTORY_LENGTH);
+        assert_eq!(save.runs[0].score, RUN_HISTORY_LENGTH as u32 * 2 - 1);
+    }
+
+    #[test]
+    fn leaderboard_keeps_the_top_runs() {
+        let mut save = SaveFile::default();
+        assert_eq!(save.enter_leaderboard(run(0)), None);
+        for score in 1..=LEADERBOARD_LENGTH as u32 {
+            assert_eq!(save.enter_leaderboard(run(score * 10)), Some(0));
+        }
+        // full: it has to beat the last one
+        assert_eq!(save.enter_leaderboard(run(10)), None);
+        let tied = RunRecord { name: "ANN".to_string(), ..run(50) };
+        assert_eq!(save.enter_leaderboard(tied), Some(6));
+        assert_eq!(save.leaderboard.len(), LEADERBOARD_LENGTH);
+        assert_eq!(save.leaderboard[5].name, DEFAULT_PROFILE);
+        assert_eq!(save.leaderboard.last().unwrap().score, 20);
+    }
+
+    #[test]
+    fn profiles_are_made_on_the_fly() {
+        let mut save = SaveFile::default();
+        save.switch_profile("ANN");
+        save.switch_profile("BOB");
+        save.switch_profile("ANN");
+        assert_eq!(save.profiles, [DEFAULT_PROFILE, "ANN", "BOB"]);
+        save.next_profile();
+        assert_eq!(save.profile, "BOB");
+        save.next_profile();
+        assert_eq!(save.profile, DEFAULT_PROFILE);
+        save.switch_profile("");
+        assert_eq!(save.profiles.len(), 3);
+    }
+
+    #[test]
+    fn daily_keeps_the_best_of_each_day() {
+        let mut save = SaveFile::default();
+        let day = NaiveDate::from_ymd_opt(2025, 3, 1).unwrap();
+        assert!(save.record_daily(day, 50, 5.));
+        assert!(!save.record_daily(day, 30, 3.));
+        assert!(save.record_daily(day.succ_opt().unwrap(), 10, 1.));
+        // not in the normal history
+        assert_eq!(save.best_score, 0);
+        assert!(save.runs.is_empty());
+        assert_eq!(save.daily[&day], DailyRecord { score: 50, seconds: 5., attempts: 2 });
+
+        for offset in 0..DAILY_HISTORY_LENGTH as u64 {
+            save.record_daily(day + chrono::Days::new(offset + 10), 1, 0.1);
+        }
+        assert_eq!(save.daily.len(), DAILY_HISTORY_LENGTH);
+        assert!(!save.daily.contains_key(&day));
+
+        let path = temp_path("daily");
+        save.save(&path).unwrap();
+        assert_eq!(SaveFile::load(&path), save);
+    }
+}
@@ -0,0 +1,424 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::actions::{Action, Bindings};
+use crate::view::ViewMode;
+use crate::{despawn_screen, score, spawn_button, ButtonSize, Despawn, GameState};
+
+// bump this when the layout of SettingsFile changes
+const SETTINGS_VERSION: u32 = 1;
+const SETTINGS_FILE_NAME: &str = "settings.ron";
+
+// how much a click on a volume changes it
+const VOLUME_STEP: f32 = 0.1;
+
+// the player's options, next to the save file; tests keep the defaults and never write
+pub struct SettingsPlugin {
+    pub save_to_disk: bool,
+}
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        let settings = if self.save_to_disk {
+            Settings::load_default()
+        } else {
+            Settings::default()
+        };
+        app.insert_resource(settings)
+            .add_systems(Update, apply_settings.run_if(resource_changed::<Settings>))
+            // the settings screen
+            .add_systems(OnEnter(GameState::Settings), setup_settings)
+            .add_systems(
+                Update,
+                (settings_buttons, update_settings)
+                    .chain()
+                    .run_if(in_state(GameState::Settings)),
+            )
+            .add_systems(OnExit(GameState::Settings), despawn_screen);
+    }
+}
+
+// every field has a default so a file from an older build still loads
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct SettingsFile {
+    pub version: u32,
+    pub bindings: Bindings,
+    // 0 to 1, the sound effects play at both multiplied
+    pub master_volume: f32,
+    pub sfx_volume: f32,
+    // m toggles it anywhere
+    pub muted: bool,
+    // off keeps the world camera upright, the timeline still plays underneath
+    pub camera_rotation: bool,
+    // no trippy flickering, slower direction changes, no blinking score
+    pub reduced_motion: bool,
+    pub high_contrast: bool,
+    pub frame_cap: FrameCap,
+    // what a window that isn't the play field's shape shows, see view.rs
+    pub view: ViewMode,
+}
+impl Default for SettingsFile {
+    fn default() -> Self {
+        SettingsFile {
+            version: SETTINGS_VERSION,
+            bindings: Bindings::default(),
+            master_volume: 1.,
+            sfx_volume: 1.,
+            muted: false,
+            camera_rotation: true,
+            reduced_motion: false,
+            high_contrast: false,
+            frame_cap: FrameCap::Auto,
+            view: ViewMode::Letterbox,
+        }
+    }
+}
+impl SettingsFile {
+    pub fn effects_volume(&self) -> f32 {
+        self.master_volume * self.sfx_volume
+    }
+
+    pub fn palette(&self) -> Palette {
+        if self.high_contrast {
+            Palette::HIGH_CONTRAST
+        } else {
+            Palette::DEFAULT
+        }
+    }
+
+    // like the save file: missing is the defaults, broken gets moved aside
+    pub fn load(path: &Path) -> SettingsFile {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return SettingsFile::default(),
+            Err(e) => {
+                warn!("couldn't read settings {}: {e}", path.display());
+                return SettingsFile::default();
+            }
+        };
+        match ron::from_str::<SettingsFile>(&text) {
+            Ok(mut settings) => {
+                settings.version = SETTINGS_VERSION;
+                settings.master_volume = settings.master_volume.clamp(0., 1.);
+                settings.sfx_volume = settings.sfx_volume.clamp(0., 1.);
+                // actions added since the file was written
+                settings.bindings.fill_defaults();
+                settings
+            }
+            Err(e) => {
+                warn!("settings {} are corrupt: {e}", path.display());
+                score::backup(path, "corrupt");
+                SettingsFile::default()
+            }
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        let text = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(std::io::Error::other)?;
+        score::write_file(path, &text)
+    }
+}
+
+// the loaded settings and where they go; no path means nothing gets written to disk
+#[derive(Resource, Default)]
+pub struct Settings {
+    pub path: Option<PathBuf>,
+    pub file: SettingsFile,
+}
+impl Settings {
+    fn load_default() -> Settings {
+        let path = score::data_path(SETTINGS_FILE_NAME);
+        Settings {
+            file: path.as_deref().map(SettingsFile::load).unwrap_or_default(),
+            path,
+        }
+    }
+
+    pub fn write(&self) {
+        if let Some(path) = &self.path {
+            if let Err(e) = self.file.save(path) {
+                warn!("couldn't write settings {}: {e}", path.display());
+            }
+        }
+    }
+}
+
+// how bevy_framepace paces frames
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameCap {
+    // the monitor's refresh rate
+    Auto,
+    Fps(u32),
+    Off,
+}
+impl FrameCap {
+    const CYCLE: [FrameCap; 6] = [
+        FrameCap::Auto,
+        FrameCap::Fps(30),
+        FrameCap::Fps(60),
+        FrameCap::Fps(120),
+        FrameCap::Fps(144),
+        FrameCap::Off,
+    ];
+
+    fn next(self) -> FrameCap {
+        let index = FrameCap::CYCLE.iter().position(|cap| *cap == self);
+        FrameCap::CYCLE[index.map_or(0, |index| (index + 1) % FrameCap::CYCLE.len())]
+    }
+
+    pub fn limiter(self) -> bevy_framepace::Limiter {
+        match self {
+            FrameCap::Auto => bevy_framepace::Limiter::Auto,
+            FrameCap::Fps(fps) => bevy_framepace::Limiter::from_framerate(fps.max(1) as f64),
+            FrameCap::Off => bevy_framepace::Limiter::Off,
+        }
+    }
+
+    fn name(self) -> String {
+        match self {
+            FrameCap::Auto => "Monitor".to_string(),
+            FrameCap::Fps(fps) => format!("{fps} fps"),
+            FrameCap::Off => "Off".to_string(),
+        }
+    }
+}
+
+// what the world is drawn in
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Palette {
+    pub background: Color,
+    pub dino: Color,
+    pub ground: Color,
+    pub ground_obstacle: Color,
+    pub flying_obstacle: Color,
+    // the pickups
+    pub shield: Color,
+    pub slow_motion: Color,
+    pub double_jump: Color,
+    // the small print in the hud and on the death screen
+    pub dim_text: Color,
+}
+impl Palette {
+    const DEFAULT: Palette = Palette {
+        background: Color::srgb(0., 0., 0.),
+        dino: Color::srgb(1., 1., 1.),
+        ground: Color::srgb(0., 1., 0.),
+        ground_obstacle: Color::srgb(1., 0., 0.),
+        flying_obstacle: Color::srgb(1., 0.5, 0.),
+        shield: Color::srgb(0.3, 0.6, 1.),
+        slow_motion: Color::srgb(0.7, 0.4, 1.),
+        double_jump: Color::srgb(1., 0.9, 0.2),
+        dim_text: Color::srgb(0.5, 0.5, 0.5),
+    };
+    // everything bright on black, and the two obstacles far apart in hue and brightness
+    const HIGH_CONTRAST: Palette = Palette {
+        background: Color::srgb(0., 0., 0.),
+        dino: Color::srgb(1., 1., 1.),
+        ground: Color::srgb(1., 1., 1.),
+        ground_obstacle: Color::srgb(1., 1., 0.),
+        flying_obstacle: Color::srgb(0., 1., 1.),
+        shield: Color::srgb(0., 0.4, 1.),
+        slow_motion: Color::srgb(1., 0., 1.),
+        double_jump: Color::srgb(0., 1., 0.),
+        dim_text: Color::srgb(0.9, 0.9, 0.9),
+    };
+}
+
+fn apply_settings(
+    settings: Res<Settings>,
+    mut clear_color: ResMut<ClearColor>,
+    // only there with a window
+    framepace: Option<ResMut<bevy_framepace::FramepaceSettings>>,
+) {
+    clear_color.0 = settings.file.palette().background;
+    if let Some(mut framepace) = framepace {
+        framepace.limiter = settings.file.frame_cap.limiter();
+    }
+}
+
+// a row on the settings screen; clicking it steps the value, wrapping around
+#[derive(Component, Clone, Copy, PartialEq, Debug)]
+enum SettingsOption {
+    MasterVolume,
+    SfxVolume,
+    Mute,
+    CameraRotation,
+    ReducedMotion,
+    HighContrast,
+    FrameCap,
+    View,
+}
+impl SettingsOption {
+    const ALL: [SettingsOption; 8] = [
+        SettingsOption::MasterVolume,
+        SettingsOption::SfxVolume,
+        SettingsOption::Mute,
+        SettingsOption::CameraRotation,
+        SettingsOption::ReducedMotion,
+        SettingsOption::HighContrast,
+        SettingsOption::FrameCap,
+        SettingsOption::View,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            SettingsOption::MasterVolume => "Master volume",
+            SettingsOption::SfxVolume => "Effects volume",
+            SettingsOption::Mute => "Mute",
+            SettingsOption::CameraRotation => "Camera rotation",
+            SettingsOption::ReducedMotion => "Reduced motion",
+            SettingsOption::HighContrast => "High contrast",
+            SettingsOption::FrameCap => "Frame rate cap",
+            SettingsOption::View => "Window shape",
+        }
+    }
+
+    fn step(self, file: &mut SettingsFile) {
+        let volume = |volume: f32| {
+            if volume >= 1. - VOLUME_STEP / 2. {
+                0.
+            } else {
+                ((volume + VOLUME_STEP) / VOLUME_STEP).round() * VOLUME_STEP
+            }
+        };
+        match self {
+            SettingsOption::MasterVolume => file.master_volume = volume(file.master_volume),
+            SettingsOption::SfxVolume => file.sfx_volume = volume(file.sfx_volume),
+            SettingsOption::Mute => file.muted = !file.muted,
+            SettingsOption::CameraRotation => file.camera_rotation = !file.camera_rotation,
+            SettingsOption::ReducedMotion => file.reduced_motion = !file.reduced_motion,
+            SettingsOption::HighContrast => file.high_contrast = !file.high_contrast,
+            SettingsOption::FrameCap => file.frame_cap = file.frame_cap.next(),
+            SettingsOption::View => file.view = file.view.next(),
+        }
+    }
+
+    fn value(self, file: &SettingsFile) -> String {
+        let on_off = |on: bool| if on { "On" } else { "Off" }.to_string();
+        match self {
+            SettingsOption::MasterVolume => format!("{:.0}%", file.master_volume * 100.),
+            SettingsOption::SfxVolume => format!("{:.0}%", file.sfx_volume * 100.),
+            SettingsOption::Mute => on_off(file.muted),
+            SettingsOption::CameraRotation => on_off(file.camera_rotation),
+            SettingsOption::ReducedMotion => on_off(file.reduced_motion),
+            SettingsOption::HighContrast => on_off(file.high_contrast),
+            SettingsOption::FrameCap => file.frame_cap.name(),
+            SettingsOption::View => file.view.name().to_string(),
+        }
+    }
+}
+
+#[derive(Component)]
+struct SettingsBack;
+
+// the value of an option, kept up to date
+#[derive(Component)]
+struct SettingsValue(SettingsOption);
+
+fn setup_settings(mut commands: Commands) {
+    commands
+    .spawn((Node {
+        width: Val::Percent(100.0),
+        height: Val::Percent(100.0),
+        flex_direction: FlexDirection::Column,
+        align_items: AlignItems::Center,
+        justify_content: JustifyContent::Center,
+        ..default()
+    }, Despawn))
+    .with_children(|parent| {
+        parent.spawn((
+            Text::new("Settings (click one to change it)"),
+            TextColor(Color::srgb(0.9, 0.9, 0.9)),
+        ));
+        for option in SettingsOption::ALL {
+            parent.spawn(Node {
+                align_items: AlignItems::Center,
+                ..default()
+            })
+            .with_children(|row| {
+                spawn_button(row, ButtonSize::Small, Val::Px(260.0), Text::new(option.name()), option);
+                row.spawn((
+                    Node {
+                        width: Val::Px(160.0),
+                        ..default()
+                    },
+                    Text::default(),
+                    TextColor(Color::srgb(0.9, 0.9, 0.9)),
+                    SettingsValue(option),
+                ));
+            });
+        }
+        spawn_button(parent, ButtonSize::Small, Val::Auto, Text::new("Back"), SettingsBack);
+    });
+}
+
+fn settings_buttons(
+    option_query: Query<(&Interaction, &SettingsOption), Changed<Interaction>>,
+    back_query: Query<&Interaction, (Changed<Interaction>, With<SettingsBack>)>,
+    mut settings: ResMut<Settings>,
+    actions: Res<ButtonInput<Action>>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    for (interaction, option) in &option_query {
+        if *interaction == Interaction::Pressed {
+            option.step(&mut settings.file);
+            settings.write();
+        }
+    }
+    if back_query.iter().any(|interaction| *interaction == Interaction::Pressed) || actions.just_pressed(Action::Back) {
+        game_state.set(GameState::Menu);
+    }
+}
+
+fn update_settings(settings: Res<Settings>, mut query: Query<(&mut Text, &SettingsValue)>) {
+    for (mut text, SettingsValue(option)) in &mut query {
+        text.0 = option.value(&settings.file);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actions::Binding;
+
+    #[test]
+    fn round_trip() {
+        let path = std::env::temp_dir().join(format!("chromedino-settings-{}.ron", std::process::id()));
+        let mut settings = SettingsFile::default();
+        settings.bindings.rebind(Action::Jump, Binding::Key(KeyCode::KeyW));
+        settings.camera_rotation = false;
+        settings.frame_cap = FrameCap::Fps(60);
+        settings.view = ViewMode::Expand;
+        settings.save(&path).unwrap();
+        assert_eq!(SettingsFile::load(&path), settings);
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn missing_actions_get_their_defaults() {
+        let settings: SettingsFile = ron::from_str("(bindings: {Jump: [Key(KeyW)]})").unwrap();
+        let mut bindings = settings.bindings;
+        bindings.fill_defaults();
+        assert_eq!(bindings.get(Action::Jump), [Binding::Key(KeyCode::KeyW)]);
+        assert_eq!(bindings.get(Action::Duck), Bindings::default().get(Action::Duck));
+    }
+
+    #[test]
+    fn options_wrap_around() {
+        let mut file = SettingsFile::default();
+        SettingsOption::MasterVolume.step(&mut file);
+        assert_eq!(file.master_volume, 0.);
+        for _ in 0..10 {
+            SettingsOption::MasterVolume.step(&mut file);
+        }
+        assert_eq!(file.master_volume, 1.);
+
+        for _ in 0..FrameCap::CYCLE.len() {
+            SettingsOption::FrameCap.step(&mut file);
+        }
+        assert_eq!(file.frame_cap, FrameCap::Auto);
+    }
+}
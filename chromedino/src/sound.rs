@@ -0,0 +1,296 @@
+#[cfg(feature = "audio")]
+use bevy::audio::{AddAudioSource, AudioPlugin, Decodable, Source, Volume};
+use bevy::prelude::*;
+#[cfg(feature = "audio")]
+use bevy::utils::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::f32::consts::TAU;
+use std::path::{Component, Path};
+#[cfg(feature = "audio")]
+use std::time::Duration;
+
+use crate::actions::Action;
+use crate::settings::Settings;
+#[cfg(feature = "audio")]
+use crate::tuning::Tuning;
+use crate::{Dino, GameState, GameplaySet, InRun};
+
+const SAMPLE_RATE: u32 = 44100;
+// the tones are loud squares, this keeps them next to a normal sample
+const TONE_VOLUME: f32 = 0.25;
+// fade in, so a tone doesn't start with a click
+const TONE_ATTACK_SECONDS: f32 = 0.005;
+// what bevy is built to decode
+const SAMPLE_EXTENSIONS: [&str; 1] = ["ogg"];
+
+// the game sends Sound events, this plays them: a sample from the tuning file if it has one and it loaded,
+// a synthesized tone otherwise, so nothing has to be shipped. without bevy's AudioPlugin (tests) or the audio
+// feature nothing plays
+pub struct SoundPlugin;
+impl Plugin for SoundPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<Sound>()
+            .init_resource::<Airborne>()
+            .add_systems(Update, toggle_mute)
+            .add_systems(FixedUpdate, dino_sounds.in_set(GameplaySet).after(crate::update_dino))
+            .add_systems(OnEnter(InRun), reset_dino_sounds)
+            .add_systems(OnEnter(GameState::Dead), |mut sounds: EventWriter<Sound>| {
+                sounds.send(Sound::Death);
+            });
+        #[cfg(feature = "audio")]
+        if app.is_plugin_added::<AudioPlugin>() {
+            app.add_audio_source::<Tone>()
+                .init_resource::<SoundHandles>()
+                .add_systems(Startup, make_tones)
+                .add_systems(Update, (load_samples.run_if(resource_changed::<Tuning>), play_sounds).chain());
+        }
+    }
+}
+
+#[derive(Event, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum Sound {
+    Jump,
+    Land,
+    Death,
+    // every 100 points
+    Milestone,
+    // the camera starts spinning
+    CameraRotation,
+    // picked up a power up
+    PowerUp,
+}
+impl Sound {
+    pub const ALL: [Sound; 6] =
+        [Sound::Jump, Sound::Land, Sound::Death, Sound::Milestone, Sound::CameraRotation, Sound::PowerUp];
+
+    #[cfg(any(feature = "audio", test))]
+    fn tone(self) -> Tone {
+        let tone = |wave, from_hz, to_hz, seconds| Tone {
+            wave,
+            from_hz,
+            to_hz,
+            seconds,
+        };
+        match self {
+            Sound::Jump => tone(Wave::Square, 440., 880., 0.1),
+            Sound::Land => tone(Wave::Triangle, 160., 80., 0.06),
+            Sound::Death => tone(Wave::Square, 440., 55., 0.5),
+            Sound::Milestone => tone(Wave::Square, 990., 1320., 0.2),
+            Sound::CameraRotation => tone(Wave::Sine, 220., 660., 0.6),
+            Sound::PowerUp => tone(Wave::Triangle, 660., 1760., 0.25),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct SoundTuning {
+    // files in the assets folder played instead of the built in tones, like {Jump: "sounds/jump.ogg"}
+    pub samples: BTreeMap<Sound, String>,
+}
+impl SoundTuning {
+    pub fn validate(&self) -> Result<(), String> {
+        for (sound, path) in &self.samples {
+            let path = Path::new(path);
+            // inside the assets folder
+            if !path.components().all(|component| matches!(component, Component::Normal(_) | Component::CurDir)) {
+                return Err(format!("sounds.samples: {sound:?} has to be a path in the assets folder"));
+            }
+            let extension = path.extension().and_then(|extension| extension.to_str());
+            if !extension.is_some_and(|extension| SAMPLE_EXTENSIONS.contains(&extension.to_lowercase().as_str())) {
+                return Err(format!("sounds.samples: {sound:?} has to be one of {}", SAMPLE_EXTENSIONS.join(", ")));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Wave {
+    Sine,
+    Square,
+    Triangle,
+}
+
+// a beep sliding from one pitch to another, fading out over its length
+#[derive(Asset, TypePath, Clone, Copy, PartialEq, Debug)]
+pub struct Tone {
+    pub wave: Wave,
+    pub from_hz: f32,
+    pub to_hz: f32,
+    pub seconds: f32,
+}
+impl Tone {
+    #[cfg(any(feature = "audio", test))]
+    fn samples(self) -> ToneDecoder {
+        ToneDecoder {
+            tone: self,
+            sample: 0,
+            phase: 0.,
+        }
+    }
+}
+#[cfg(feature = "audio")]
+impl Decodable for Tone {
+    type DecoderItem = f32;
+    type Decoder = ToneDecoder;
+
+    fn decoder(&self) -> ToneDecoder {
+        self.samples()
+    }
+}
+
+pub struct ToneDecoder {
+    tone: Tone,
+    sample: u32,
+    // in turns
+    phase: f32,
+}
+impl ToneDecoder {
+    fn length(&self) -> u32 {
+        (self.tone.seconds * SAMPLE_RATE as f32) as u32
+    }
+}
+impl Iterator for ToneDecoder {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.sample >= self.length() {
+            return None;
+        }
+        let tone = &self.tone;
+        let t = self.sample as f32 / SAMPLE_RATE as f32;
+        let hz = tone.from_hz.lerp(tone.to_hz, t / tone.seconds);
+        self.phase = (self.phase + hz / SAMPLE_RATE as f32).fract();
+        self.sample += 1;
+
+        let value = match tone.wave {
+            Wave::Sine => (self.phase * TAU).sin(),
+            Wave::Square => if self.phase < 0.5 { 1. } else { -1. },
+            Wave::Triangle => 1. - 4. * (self.phase - 0.5).abs(),
+        };
+        let envelope = (t / TONE_ATTACK_SECONDS).min(1.) * (1. - t / tone.seconds);
+        Some(value * envelope * TONE_VOLUME)
+    }
+}
+#[cfg(feature = "audio")]
+impl Source for ToneDecoder {
+    fn current_frame_len(&self) -> Option<usize> {
+        Some((self.length() - self.sample) as usize)
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        Some(Duration::from_secs_f32(self.tone.seconds))
+    }
+}
+
+#[cfg(feature = "audio")]
+#[derive(Resource, Default)]
+struct SoundHandles {
+    tones: HashMap<Sound, Handle<Tone>>,
+    samples: HashMap<Sound, Handle<AudioSource>>,
+}
+
+#[cfg(feature = "audio")]
+fn make_tones(mut handles: ResMut<SoundHandles>, mut tones: ResMut<Assets<Tone>>) {
+    for sound in Sound::ALL {
+        handles.tones.insert(sound, tones.add(sound.tone()));
+    }
+}
+
+// again whenever the tuning file changes
+#[cfg(feature = "audio")]
+fn load_samples(mut handles: ResMut<SoundHandles>, tuning: Res<Tuning>, asset_server: Res<AssetServer>) {
+    handles.samples = tuning
+        .sounds
+        .samples
+        .iter()
+        .map(|(sound, path)| (*sound, asset_server.load(path.clone())))
+        .collect();
+}
+
+#[cfg(feature = "audio")]
+fn play_sounds(
+    mut commands: Commands,
+    mut sounds: EventReader<Sound>,
+    handles: Res<SoundHandles>,
+    asset_server: Res<AssetServer>,
+    settings: Res<Settings>,
+    game_state: Res<State<GameState>>,
+) {
+    // the demo plays behind the menu without a sound
+    let volume = settings.file.effects_volume();
+    if settings.file.muted || volume <= 0. || *game_state == GameState::Demo {
+        sounds.clear();
+        return;
+    }
+    let settings = PlaybackSettings::DESPAWN.with_volume(Volume::new(volume));
+    let mut played = Vec::new();
+    for sound in sounds.read() {
+        // once a frame is enough
+        if played.contains(sound) {
+            continue;
+        }
+        played.push(*sound);
+        // a sample that's still loading or failed to load falls back to the tone
+        match handles.samples.get(sound) {
+            Some(sample) if asset_server.is_loaded(sample) => {
+                commands.spawn((AudioPlayer(sample.clone()), settings));
+            }
+            _ => {
+                if let Some(tone) = handles.tones.get(sound) {
+                    commands.spawn((AudioPlayer(tone.clone()), settings));
+                }
+            }
+        }
+    }
+}
+
+fn toggle_mute(actions: Res<ButtonInput<Action>>, mut settings: ResMut<Settings>) {
+    if actions.just_pressed(Action::Mute) {
+        settings.file.muted = !settings.file.muted;
+        settings.write();
+    }
+}
+
+// whether the dino was in the air last tick
+#[derive(Resource, Default)]
+struct Airborne(bool);
+
+fn reset_dino_sounds(mut airborne: ResMut<Airborne>) {
+    airborne.0 = false;
+}
+
+// takes off and lands, from the outside so the gameplay doesn't need to know about sound
+fn dino_sounds(dino_query: Query<&Dino>, mut airborne: ResMut<Airborne>, mut sounds: EventWriter<Sound>) {
+    let Ok(dino) = dino_query.get_single() else { return };
+    if dino.jumped != airborne.0 {
+        sounds.send(if dino.jumped { Sound::Jump } else { Sound::Land });
+    }
+    airborne.0 = dino.jumped;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tones_fade_out_and_end() {
+        for sound in Sound::ALL {
+            let tone = sound.tone();
+            let samples: Vec<f32> = tone.samples().collect();
+            assert_eq!(samples.len(), (tone.seconds * SAMPLE_RATE as f32) as usize);
+            assert!(samples.iter().all(|sample| sample.abs() <= TONE_VOLUME));
+            assert!(samples.last().unwrap().abs() < 0.01);
+        }
+    }
+}
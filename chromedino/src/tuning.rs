@@ -0,0 +1,363 @@
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, AssetLoadFailedEvent, LoadContext};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::choreography::{CameraRotationPhase, CameraStep};
+use crate::collision::CollisionTuning;
+use crate::difficulty::{DifficultyTuning, GameMode};
+use crate::patterns::PatternTuning;
+use crate::power_ups::PowerUpTuning;
+use crate::sound::SoundTuning;
+
+// relative to the assets folder; edits to it are picked up while the game runs
+const TUNING_FILE: &str = "game.tuning.ron";
+
+// the numbers designers play with; without an asset server (tests) the defaults are used
+pub struct TuningPlugin;
+impl Plugin for TuningPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Tuning>();
+        if !app.is_plugin_added::<AssetPlugin>() {
+            return;
+        }
+        app.init_asset::<Tuning>()
+            .register_asset_loader(TuningLoader)
+            .add_systems(Startup, load_tuning)
+            .add_systems(PreUpdate, (apply_tuning, report_tuning_errors));
+    }
+}
+
+// every field has a default so a file only needs the values it changes
+#[derive(Asset, TypePath, Resource, Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct Tuning {
+    pub jump: JumpTuning,
+    pub speed: SpeedTuning,
+    pub obstacles: ObstacleTuning,
+    pub camera: CameraTuning,
+    pub patterns: PatternTuning,
+    pub collision: CollisionTuning,
+    pub power_ups: PowerUpTuning,
+    pub sounds: SoundTuning,
+    // a set of curves per game mode
+    pub difficulty: DifficultyTuning,
+}
+
+// how the jump feels
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct JumpTuning {
+    pub jump_force: f32,
+    pub gravity: f32,
+    // letting go of jump while rising faster than this cuts the speed down to it, short taps make short hops
+    pub jump_cut_velocity: f32,
+    // gravity multiplier while down is held in the air
+    pub fast_fall_gravity_scale: f32,
+    // how long after leaving the ground a jump still works
+    pub coyote_seconds: f32,
+    // how long a jump pressed in the air is kept for the landing
+    pub jump_buffer_seconds: f32,
+}
+impl Default for JumpTuning {
+    fn default() -> Self {
+        JumpTuning {
+            jump_force: 500.,
+            gravity: 1300.,
+            jump_cut_velocity: 300.,
+            fast_fall_gravity_scale: 3.,
+            coyote_seconds: 0.08,
+            jump_buffer_seconds: 0.1,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct SpeedTuning {
+    pub seconds_until_full_speed: f32,
+    pub scroll_speed_min: f32,
+    pub scroll_speed_max: f32,
+}
+impl SpeedTuning {
+    // pixels per second at a game speed (0 to 1)
+    pub fn scroll_speed(&self, game_speed: f32) -> f32 {
+        (self.scroll_speed_max - self.scroll_speed_min) * game_speed + self.scroll_speed_min
+    }
+}
+impl Default for SpeedTuning {
+    fn default() -> Self {
+        SpeedTuning {
+            seconds_until_full_speed: 60.,
+            scroll_speed_min: 300.,
+            scroll_speed_max: 500.,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct ObstacleTuning {
+    pub width_min: f32,
+    pub width_max: f32,
+    pub height_min: f32,
+    pub height_max: f32,
+    pub spacing: f32,
+    // in both directions; multiplier changes with full-speed-timer
+    pub spacing_max: f32,
+    pub flyer_size: Vec2,
+    // bottom edge above the ground: jump over, duck under, run under
+    pub flyer_heights: Vec<f32>,
+    // scaled by the flyers difficulty curve
+    pub flyer_chance_max: f32,
+}
+impl Default for ObstacleTuning {
+    fn default() -> Self {
+        ObstacleTuning {
+            width_min: 20.,
+            width_max: 50.,
+            height_min: 30.,
+            height_max: 74.,
+            spacing: 500.,
+            spacing_max: 125.,
+            flyer_size: Vec2::new(40., 24.),
+            flyer_heights: vec![10., 40., 75.],
+            flyer_chance_max: 0.3,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct CameraTuning {
+    // how long the camera takes to swing around when the spin changes direction
+    pub turn_seconds: f32,
+    // from the start of the run; the last step repeats
+    pub timeline: Vec<CameraStep>,
+    // the same, for the daily challenge
+    pub daily_timeline: Vec<CameraStep>,
+}
+impl Default for CameraTuning {
+    fn default() -> Self {
+        let step = |seconds, phase, rotations| CameraStep {
+            seconds,
+            phase,
+            rotations,
+            ..default()
+        };
+        CameraTuning {
+            turn_seconds: 0.5,
+            // calm for a while, then weirder and weirder
+            timeline: vec![
+                step(100., CameraRotationPhase::Normal, 0.),
+                step(20., CameraRotationPhase::Normal, 5.),
+                step(20., CameraRotationPhase::ChangeDirectionWhenTimer, 3.),
+                step(20., CameraRotationPhase::ChangeDirectionWhenJump, 4.),
+                step(5., CameraRotationPhase::Trippy, 1.),
+                step(20., CameraRotationPhase::ChangeDirectionWhenJump, 5.),
+            ],
+            daily_timeline: vec![
+                step(60., CameraRotationPhase::Normal, 0.),
+                step(20., CameraRotationPhase::Normal, 3.),
+                step(20., CameraRotationPhase::ChangeDirectionWhenTimer, 2.),
+                step(20., CameraRotationPhase::ChangeDirectionWhenJump, 3.),
+            ],
+        }
+    }
+}
+
+impl CameraTuning {
+    pub fn timeline(&self, mode: GameMode) -> &[CameraStep] {
+        if mode == GameMode::Daily {
+            &self.daily_timeline
+        } else {
+            &self.timeline
+        }
+    }
+}
+
+impl Tuning {
+    pub fn from_ron(text: &str) -> Result<Tuning, TuningError> {
+        let tuning: Tuning = ron::from_str(text).map_err(TuningError::Parse)?;
+        tuning.validate()?;
+        Ok(tuning)
+    }
+
+    // catches the values that would panic or divide by zero somewhere in the game
+    pub fn validate(&self) -> Result<(), TuningError> {
+        let invalid = |message: &str| Err(TuningError::Invalid(message.to_string()));
+        let Tuning { jump, speed, obstacles, camera, patterns, collision, power_ups, sounds, difficulty } = self;
+
+        if jump.gravity <= 0. || jump.fast_fall_gravity_scale <= 0. {
+            return invalid("gravity has to be above 0");
+        }
+        if jump.coyote_seconds < 0. || jump.jump_buffer_seconds < 0. {
+            return invalid("jump.coyote_seconds and jump.jump_buffer_seconds can't be negative");
+        }
+        if speed.seconds_until_full_speed <= 0. {
+            return invalid("speed.seconds_until_full_speed has to be above 0");
+        }
+        if speed.scroll_speed_min > speed.scroll_speed_max {
+            return invalid("speed.scroll_speed_min is above speed.scroll_speed_max");
+        }
+        if obstacles.width_min <= 0. || obstacles.width_min >= obstacles.width_max {
+            return invalid("obstacles.width_min has to be above 0 and below obstacles.width_max");
+        }
+        if obstacles.height_min <= 0. || obstacles.height_min >= obstacles.height_max {
+            return invalid("obstacles.height_min has to be above 0 and below obstacles.height_max");
+        }
+        if obstacles.spacing_max <= 0. {
+            return invalid("obstacles.spacing_max has to be above 0");
+        }
+        if obstacles.flyer_heights.is_empty() {
+            return invalid("obstacles.flyer_heights is empty");
+        }
+        if camera.turn_seconds < 0. {
+            return invalid("camera.turn_seconds can't be negative");
+        }
+        for (name, timeline) in [("timeline", &camera.timeline), ("daily_timeline", &camera.daily_timeline)] {
+            if timeline.is_empty() {
+                return Err(TuningError::Invalid(format!("camera.{name} is empty")));
+            }
+            if timeline.iter().any(|step| step.seconds <= 0.) {
+                return Err(TuningError::Invalid(format!("every step in camera.{name} has to last above 0 seconds")));
+            }
+        }
+        if collision.forgiveness < 0. {
+            return invalid("collision.forgiveness can't be negative");
+        }
+        patterns.validate().map_err(TuningError::Invalid)?;
+        power_ups.validate().map_err(TuningError::Invalid)?;
+        sounds.validate().map_err(TuningError::Invalid)?;
+        difficulty.validate().map_err(TuningError::Invalid)
+    }
+}
+
+#[derive(Debug)]
+pub enum TuningError {
+    Io(std::io::Error),
+    Parse(ron::error::SpannedError),
+    Invalid(String),
+}
+impl fmt::Display for TuningError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TuningError::Io(e) => write!(f, "{e}"),
+            TuningError::Parse(e) => write!(f, "{e}"),
+            TuningError::Invalid(message) => write!(f, "{message}"),
+        }
+    }
+}
+impl std::error::Error for TuningError {}
+impl From<std::io::Error> for TuningError {
+    fn from(e: std::io::Error) -> Self {
+        TuningError::Io(e)
+    }
+}
+
+#[derive(Default)]
+struct TuningLoader;
+impl AssetLoader for TuningLoader {
+    type Asset = Tuning;
+    type Settings = ();
+    type Error = TuningError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Tuning, TuningError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let text = String::from_utf8(bytes).map_err(|e| TuningError::Invalid(e.to_string()))?;
+        Tuning::from_ron(&text)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["tuning.ron"]
+    }
+}
+
+// keeps the file loaded (and watched)
+#[derive(Resource)]
+struct TuningHandle(Handle<Tuning>);
+
+fn load_tuning(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(TuningHandle(asset_server.load(TUNING_FILE)));
+}
+
+// a reloaded file replaces the whole resource; a broken one never gets here
+fn apply_tuning(
+    mut events: EventReader<AssetEvent<Tuning>>,
+    assets: Res<Assets<Tuning>>,
+    handle: Res<TuningHandle>,
+    mut tuning: ResMut<Tuning>,
+) {
+    for event in events.read() {
+        if let AssetEvent::Added { id } | AssetEvent::Modified { id } = event {
+            if *id != handle.0.id() {
+                continue;
+            }
+            if let Some(loaded) = assets.get(*id) {
+                info!("loaded tuning from {TUNING_FILE}");
+                *tuning = loaded.clone();
+            }
+        }
+    }
+}
+
+fn report_tuning_errors(mut events: EventReader<AssetLoadFailedEvent<Tuning>>) {
+    for event in events.read() {
+        error!("keeping the previous tuning, {} is invalid: {}", event.path, event.error);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shipped_file_matches_defaults() {
+        let text = include_str!("../assets/game.tuning.ron");
+        assert_eq!(Tuning::from_ron(text).unwrap(), Tuning::default());
+    }
+
+    #[test]
+    fn missing_values_are_defaults() {
+        let tuning = Tuning::from_ron("(jump: (gravity: 2000))").unwrap();
+        assert_eq!(tuning.jump.gravity, 2000.);
+        assert_eq!(tuning.jump.jump_force, JumpTuning::default().jump_force);
+        assert_eq!(tuning.camera, CameraTuning::default());
+    }
+
+    #[test]
+    fn rejects_bad_files() {
+        assert!(matches!(Tuning::from_ron("(jump: (gravity: \"lots\"))"), Err(TuningError::Parse(_))));
+        assert!(matches!(
+            Tuning::from_ron("(obstacles: (width_min: 60, width_max: 50))"),
+            Err(TuningError::Invalid(_))
+        ));
+        assert!(matches!(Tuning::from_ron("(obstacles: (flyer_heights: []))"), Err(TuningError::Invalid(_))));
+        assert!(matches!(Tuning::from_ron("(camera: (timeline: []))"), Err(TuningError::Invalid(_))));
+        assert!(matches!(Tuning::from_ron("(camera: (daily_timeline: []))"), Err(TuningError::Invalid(_))));
+        assert!(matches!(
+            Tuning::from_ron("(patterns: (authored: [(weight: 1, obstacles: [])]))"),
+            Err(TuningError::Invalid(_))
+        ));
+        assert!(matches!(
+            Tuning::from_ron("(camera: (timeline: [(seconds: 0)]))"),
+            Err(TuningError::Invalid(_))
+        ));
+        assert!(matches!(Tuning::from_ron("(collision: (forgiveness: -1))"), Err(TuningError::Invalid(_))));
+        assert!(matches!(Tuning::from_ron("(power_ups: (heights: []))"), Err(TuningError::Invalid(_))));
+        for path in ["", "/sounds/jump.ogg", "../jump.ogg", "sounds/jump", "sounds/jump.txt"] {
+            assert!(matches!(
+                Tuning::from_ron(&format!("(sounds: (samples: {{Jump: {path:?}}}))")),
+                Err(TuningError::Invalid(_))
+            ));
+        }
+        assert!(Tuning::from_ron("(sounds: (samples: {Jump: \"sounds/jump.ogg\"}))").is_ok());
+    }
+}
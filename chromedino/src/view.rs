@@ -0,0 +1,139 @@
+use bevy::prelude::*;
+use bevy::render::camera::{ScalingMode, Viewport};
+use bevy::render::view::RenderLayers;
+use bevy::window::{PrimaryWindow, WindowPlugin, WindowResized};
+use serde::{Deserialize, Serialize};
+
+use crate::settings::Settings;
+use crate::{GameConfig, WorldCamera};
+
+// how far expand goes before the bars come back, width over height: 21:9 wide, 4:3 tall
+const EXPAND_ASPECT_MAX: f32 = 21. / 9.;
+const EXPAND_ASPECT_MIN: f32 = 4. / 3.;
+
+// the world camera always shows the play field from the config, whatever size the window is;
+// headless there's no window and nothing to fit
+pub struct ViewPlugin;
+impl Plugin for ViewPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<WindowPlugin>() {
+            return;
+        }
+        app.add_systems(Startup, setup_bars).add_systems(
+            Update,
+            fit_camera.run_if(on_event::<WindowResized>.or(resource_changed::<Settings>)),
+        );
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ViewMode {
+    // exactly the play field, bars around it
+    #[default]
+    Letterbox,
+    // the play field and whatever else fits, up to a point
+    Expand,
+}
+impl ViewMode {
+    pub fn next(self) -> ViewMode {
+        match self {
+            ViewMode::Letterbox => ViewMode::Expand,
+            ViewMode::Expand => ViewMode::Letterbox,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ViewMode::Letterbox => "Letterbox",
+            ViewMode::Expand => "Expand",
+        }
+    }
+
+    fn aspects(self, play_field: Vec2) -> (f32, f32) {
+        match self {
+            ViewMode::Letterbox => (play_field.x / play_field.y, play_field.x / play_field.y),
+            ViewMode::Expand => (EXPAND_ASPECT_MIN, EXPAND_ASPECT_MAX),
+        }
+    }
+
+    // the part of the world shown in a window of that aspect
+    pub fn visible(self, play_field: Vec2, window_aspect: f32) -> Vec2 {
+        let (min, max) = self.aspects(play_field);
+        let aspect = window_aspect.clamp(min, max);
+        if aspect >= play_field.x / play_field.y {
+            Vec2::new(play_field.y * aspect, play_field.y)
+        } else {
+            Vec2::new(play_field.x, play_field.x / aspect)
+        }
+    }
+}
+
+// how far from the middle the world can be seen, in any mode and window, with the camera turned any way;
+// obstacles spawn and despawn past it. it doesn't follow the actual window so a run plays out the same everywhere
+// (replays, the gym), and not the actual angle either since the spin is per frame and would pop things in mid turn
+pub fn reach(play_field: Vec2) -> f32 {
+    let widest = ViewMode::Expand.visible(play_field, f32::INFINITY);
+    let tallest = ViewMode::Expand.visible(play_field, 0.);
+    widest.length().max(tallest.length()) / 2.
+}
+
+// clears the whole window under the world camera, which only draws inside its viewport
+fn setup_bars(mut commands: Commands) {
+    commands.spawn((
+        Camera2d,
+        Camera {
+            order: -1,
+            clear_color: ClearColorConfig::Custom(Color::BLACK),
+            ..default()
+        },
+        RenderLayers::none(),
+    ));
+}
+
+fn fit_camera(
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    mut camera_query: Query<(&mut Camera, &mut OrthographicProjection), With<WorldCamera>>,
+    config: Res<GameConfig>,
+    settings: Res<Settings>,
+) {
+    let Ok(window) = window_query.get_single() else { return };
+    let window_size = window.physical_size();
+    // minimized
+    if window_size.min_element() == 0 {
+        return;
+    }
+    let visible = settings.file.view.visible(config.play_field, window_size.x as f32 / window_size.y as f32);
+
+    // the biggest box of that aspect that fits, centered
+    let scale = (window_size.as_vec2() / visible).min_element();
+    let size = (visible * scale).round().as_uvec2().min(window_size);
+    for (mut camera, mut projection) in &mut camera_query {
+        camera.viewport = Some(Viewport {
+            physical_position: (window_size - size) / 2,
+            physical_size: size,
+            ..default()
+        });
+        projection.scaling_mode = ScalingMode::Fixed {
+            width: visible.x,
+            height: visible.y,
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expand_shows_more_up_to_a_point() {
+        let play_field = Vec2::new(1280., 720.);
+        assert_eq!(ViewMode::Letterbox.visible(play_field, 3.), play_field);
+        assert_eq!(ViewMode::Letterbox.visible(play_field, 1.), play_field);
+        assert_eq!(ViewMode::Expand.visible(play_field, 16. / 9.), play_field);
+        assert_eq!(ViewMode::Expand.visible(play_field, 2.), Vec2::new(1440., 720.));
+        assert_eq!(ViewMode::Expand.visible(play_field, 10.), Vec2::new(720. * EXPAND_ASPECT_MAX, 720.));
+        assert_eq!(ViewMode::Expand.visible(play_field, 1.), Vec2::new(1280., 960.));
+        // the corner of the widest view
+        assert!(reach(play_field) > Vec2::new(720. * EXPAND_ASPECT_MAX, 720.).length() / 2. - 0.01);
+    }
+}
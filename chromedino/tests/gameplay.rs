@@ -0,0 +1,851 @@
+// drives the whole game headless, one fixed tick per update
+
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::input::{ButtonState, InputPlugin};
+use bevy::prelude::*;
+use bevy::state::app::StatesPlugin;
+use bevy::time::TimeUpdateStrategy;
+use bevy::window::WindowFocused;
+use std::time::Duration;
+
+use chromedino::autoplay::{Agent, AgentView, Autopilot, RuleAgent};
+use chromedino::difficulty::GameMode;
+use chromedino::rng::GameRng;
+use chromedino::score::{RunScore, SaveData};
+use chromedino::settings::Settings;
+use chromedino::sound::Sound;
+use chromedino::choreography::{CameraRotationPhase, CameraStep};
+use chromedino::collision::Hitbox;
+use chromedino::daily;
+use chromedino::ghost::{BestRun, Ghost};
+use chromedino::power_ups::{PowerUp, PowerUps};
+use chromedino::replay::{Recording, ReplayDesync, ReplaySource};
+use chromedino::tuning::Tuning;
+use chromedino::*;
+
+const SEED: u64 = 7;
+// plenty to reach the first obstacle
+const MAX_TICKS: u32 = 60 * TICKS_PER_SECOND as u32;
+
+fn app() -> App {
+    app_at(Duration::from_secs_f64(1. / TICKS_PER_SECOND))
+}
+
+// with frames this long, one tick per frame being the usual
+fn app_at(frame: Duration) -> App {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, StatesPlugin, InputPlugin))
+        .add_plugins(GamePlugin {
+            config: GameConfig {
+                save_to_disk: false,
+                ..default()
+            },
+        })
+        .insert_resource(TimeUpdateStrategy::ManualDuration(frame));
+    app.world_mut().resource_mut::<GameRng>().fixed_seed = Some(SEED);
+    app.update();
+    app
+}
+
+fn state(app: &App) -> GameState {
+    app.world().resource::<State<GameState>>().get().clone()
+}
+
+fn run_state(app: &App) -> RunState {
+    app.world().resource::<State<RunState>>().get().clone()
+}
+
+fn run_tick(app: &App) -> u32 {
+    **app.world().resource::<RunTick>()
+}
+
+fn send_key(app: &mut App, key_code: KeyCode, state: ButtonState) {
+    // what the keys type, for the name entry
+    let logical_key = match key_code {
+        KeyCode::Space => Key::Space,
+        KeyCode::Escape => Key::Escape,
+        KeyCode::Enter => Key::Enter,
+        KeyCode::Backspace => Key::Backspace,
+        _ => Key::Unidentified(bevy::input::keyboard::NativeKey::Unidentified),
+    };
+    app.world_mut().send_event(KeyboardInput {
+        key_code,
+        logical_key,
+        state,
+        repeat: false,
+        window: Entity::PLACEHOLDER,
+    });
+    app.update();
+}
+
+// a press and a release, one update each
+fn press(app: &mut App, key_code: KeyCode) {
+    send_key(app, key_code, ButtonState::Pressed);
+    send_key(app, key_code, ButtonState::Released);
+}
+
+// presses the button with that label, the way the ui would
+fn click(app: &mut App, label: &str) {
+    let world = app.world_mut();
+    let button = world
+        .query::<(&Text, &Parent)>()
+        .iter(world)
+        .find(|(text, _)| text.0 == label)
+        .map(|(_, parent)| parent.get())
+        .unwrap();
+    *world.get_mut::<Interaction>(button).unwrap() = Interaction::Pressed;
+    app.update();
+}
+
+fn hud_score(app: &mut App) -> String {
+    let world = app.world_mut();
+    world
+        .query::<&Text>()
+        .iter(world)
+        .find(|text| text.0.starts_with("HI "))
+        .unwrap()
+        .0
+        .clone()
+}
+
+fn run_until(app: &mut App, mut done: impl FnMut(&mut App) -> bool) {
+    for _ in 0..MAX_TICKS {
+        if done(app) {
+            return;
+        }
+        app.update();
+    }
+    panic!("gave up after {MAX_TICKS} ticks");
+}
+
+fn count<F: bevy::ecs::query::QueryFilter>(app: &mut App) -> usize {
+    let world = app.world_mut();
+    world.query_filtered::<(), F>().iter(world).count()
+}
+
+// the obstacle closest to the dino that it hasn't passed yet
+fn next_obstacle(app: &mut App) -> Entity {
+    let world = app.world_mut();
+    world
+        .query_filtered::<(Entity, &Position, &Hitbox), With<Obstacle>>()
+        .iter(world)
+        .filter(|(_, position, hitbox)| position.x + hitbox.bounds().max.x > -DINO_WIDTH / 2.)
+        .min_by(|(_, a, _), (_, b, _)| a.x.total_cmp(&b.x))
+        .map(|(entity, ..)| entity)
+        .unwrap()
+}
+
+fn obstacle_x(app: &App, obstacle: Entity) -> f32 {
+    app.world().get::<Position>(obstacle).unwrap().x
+}
+
+// how high the dino's feet are and how fast it's going up
+fn dino(app: &mut App) -> (f32, f32) {
+    let world = app.world_mut();
+    let (dino, position) = world.query::<(&Dino, &Position)>().single(world);
+    (position.y - dino.size().y / 2. - PLAIN_HEIGHT, dino.velocity)
+}
+
+fn jump_apex(hold_ticks: u32) -> f32 {
+    let mut app = app();
+    press(&mut app, KeyCode::Space);
+
+    send_key(&mut app, KeyCode::Space, ButtonState::Pressed);
+    for _ in 0..hold_ticks {
+        app.update();
+    }
+    send_key(&mut app, KeyCode::Space, ButtonState::Released);
+
+    let mut apex = 0f32;
+    for _ in 0..TICKS_PER_SECOND as u32 {
+        apex = apex.max(dino(&mut app).0);
+        app.update();
+    }
+    apex
+}
+
+#[test]
+fn dino_that_never_jumps_dies_at_first_obstacle() {
+    let mut app = app();
+    press(&mut app, KeyCode::Space);
+    assert_eq!(state(&app), GameState::Game);
+
+    let first = next_obstacle(&mut app);
+    run_until(&mut app, |app| state(app) == GameState::Dead);
+
+    // it ran into the first one, not anything after it
+    let width = app.world().get::<Hitbox>(first).unwrap().size().x;
+    assert!(obstacle_x(&app, first).abs() < (width + DINO_WIDTH) / 2.);
+}
+
+#[test]
+fn shield_breaks_one_obstacle() {
+    let mut app = app();
+    press(&mut app, KeyCode::Space);
+    app.world_mut().resource_mut::<PowerUps>().grant(PowerUp::Shield, 100.);
+
+    // runs through the first one, which is gone, and dies on the next
+    let first = next_obstacle(&mut app);
+    run_until(&mut app, |app| app.world().get_entity(first).is_err());
+    assert_eq!(state(&app), GameState::Game);
+    assert!(!app.world().resource::<PowerUps>().is_active(PowerUp::Shield));
+    run_until(&mut app, |app| state(app) == GameState::Dead);
+}
+
+#[test]
+fn ghost_races_the_best_run() {
+    let mut app = app();
+    press(&mut app, KeyCode::Space);
+    run_until(&mut app, |app| state(app) == GameState::Dead);
+    let died_at = run_tick(&app);
+    let distance = app.world().resource::<RunScore>().distance;
+    // skips the name, then back to the menu
+    press(&mut app, KeyCode::Escape);
+    press(&mut app, KeyCode::Escape);
+
+    // the same run again, this time with a shield: the ghost dies where the first run did, the player goes on
+    click(&mut app, "Race your best");
+    app.update();
+    assert_eq!(state(&app), GameState::Game);
+    assert_eq!(count::<With<Ghost>>(&mut app), 1);
+    app.world_mut().resource_mut::<PowerUps>().grant(PowerUp::Shield, 100.);
+    let first = next_obstacle(&mut app);
+    // on its recording, not on whatever the player's shield did to the obstacles
+    run_until(&mut app, |app| run_tick(app) >= died_at);
+    let world = app.world_mut();
+    let ghost = world.query::<&Ghost>().single(world);
+    assert!(ghost.dead);
+    assert!((ghost.distance - distance).abs() < 1.);
+    run_until(&mut app, |app| app.world().get_entity(first).is_err());
+    for _ in 0..10 {
+        app.update();
+    }
+    assert_eq!(state(&app), GameState::Game);
+
+    let world = app.world_mut();
+    let ghost = world.query::<&Ghost>().single(world);
+    assert!(ghost.dead);
+    assert!(ghost.lead(world.resource::<RunScore>()) > 0);
+}
+
+#[test]
+fn daily_challenge_has_the_seed_of_the_day() {
+    let mut app = app();
+    *app.world_mut().resource_mut::<GameMode>() = GameMode::Hardcore;
+    click(&mut app, "Daily Challenge");
+    app.update();
+    assert_eq!(state(&app), GameState::Game);
+    assert_eq!(*app.world().resource::<GameMode>(), GameMode::Daily);
+    // over the fixed one
+    assert_eq!(app.world().resource::<GameRng>().seed, daily::seed_for(daily::today()));
+
+    run_until(&mut app, |app| state(app) == GameState::Dead);
+    let save = app.world().resource::<SaveData>();
+    assert_eq!(save.file.daily[&daily::today()].attempts, 1);
+    assert!(save.file.runs.is_empty());
+    // the first of the day is its best, but not the one the ghost races
+    assert!(save.last_run_was_best);
+    assert!(app.world().resource::<BestRun>().0.is_none());
+    app.update();
+    let world = app.world_mut();
+    assert!(world.query::<&Text>().iter(world).any(|text| text.0.starts_with("New best")));
+
+    // play again is the same course, the same score doesn't beat it
+    press(&mut app, KeyCode::Space);
+    assert_eq!(app.world().resource::<GameRng>().seed, daily::seed_for(daily::today()));
+    run_until(&mut app, |app| state(app) == GameState::Dead);
+    let save = app.world().resource::<SaveData>();
+    assert_eq!(save.file.daily[&daily::today()].attempts, 2);
+    assert!(!save.last_run_was_best);
+
+    press(&mut app, KeyCode::Escape);
+    assert_eq!(state(&app), GameState::Menu);
+    assert_eq!(*app.world().resource::<GameMode>(), GameMode::Hardcore);
+}
+
+#[test]
+fn jump_clears_smallest_obstacle() {
+    let mut app = app();
+    press(&mut app, KeyCode::Space);
+
+    let first = next_obstacle(&mut app);
+    let obstacles = Tuning::default().obstacles;
+    let size = Vec2::new(obstacles.width_min, obstacles.height_min);
+    *app.world_mut().get_mut::<Hitbox>(first).unwrap() = Hitbox::rect(size);
+    app.world_mut().get_mut::<Position>(first).unwrap().y = PLAIN_HEIGHT + size.y / 2.;
+
+    // the top of a full jump is about 0.38s in, the obstacle moves a bit over 300px/s
+    run_until(&mut app, |app| obstacle_x(app, first) < 115.);
+    send_key(&mut app, KeyCode::Space, ButtonState::Pressed);
+    run_until(&mut app, |app| obstacle_x(app, first) < -(size.x + DINO_WIDTH));
+
+    assert_eq!(state(&app), GameState::Game);
+    // and it counts
+    assert_eq!(app.world().resource::<RunScore>().cleared, 1);
+}
+
+#[test]
+fn held_jump_goes_higher_than_tap() {
+    let tap = jump_apex(0);
+    let held = jump_apex(TICKS_PER_SECOND as u32 / 2);
+    assert!(held > tap + 20., "held {held}, tap {tap}");
+}
+
+#[test]
+fn jump_pressed_just_before_landing_is_buffered() {
+    let mut app = app();
+    press(&mut app, KeyCode::Space);
+
+    press(&mut app, KeyCode::Space);
+    run_until(&mut app, |app| {
+        let (height, velocity) = dino(app);
+        velocity < 0. && height < 8.
+    });
+    // still in the air when this lands
+    press(&mut app, KeyCode::Space);
+
+    run_until(&mut app, |app| dino(app).0 == 0.);
+    run_until(&mut app, |app| dino(app).1 > 0.);
+}
+
+// ducking drops the dino from standing height, so for a moment it's off the ground without having jumped
+fn jump_after_ducking(ticks: u32) -> App {
+    let mut app = app();
+    press(&mut app, KeyCode::Space);
+    send_key(&mut app, KeyCode::ArrowDown, ButtonState::Pressed);
+    for _ in 1..ticks {
+        app.update();
+    }
+    assert!(dino(&mut app).0 > 0., "landed already");
+    // still down, standing up would put its feet back on the ground
+    press(&mut app, KeyCode::Space);
+    app
+}
+
+#[test]
+fn jump_just_after_leaving_the_ground_still_works() {
+    let coyote_ticks = (Tuning::default().jump.coyote_seconds * TICKS_PER_SECOND as f32) as u32;
+
+    let mut app = jump_after_ducking(coyote_ticks - 2);
+    assert!(dino(&mut app).1 > 0.);
+
+    // too late, it waits for the landing like any jump pressed in the air
+    let mut app = jump_after_ducking(coyote_ticks + 4);
+    assert!(dino(&mut app).1 < 0.);
+    run_until(&mut app, |app| dino(app).1 > 0.);
+}
+
+#[test]
+fn duck_under_flyer() {
+    let mut app = app();
+    press(&mut app, KeyCode::Space);
+
+    // the middle height is too low to run under and too high to easily jump
+    let first = next_obstacle(&mut app);
+    let Tuning { obstacles, .. } = Tuning::default();
+    let (size, height) = (obstacles.flyer_size, obstacles.flyer_heights[1]);
+    *app.world_mut().get_mut::<ObstacleKind>(first).unwrap() = ObstacleKind::Flying;
+    *app.world_mut().get_mut::<Hitbox>(first).unwrap() = ObstacleKind::Flying.hitbox(size);
+    app.world_mut().get_mut::<Position>(first).unwrap().y = PLAIN_HEIGHT + height + size.y / 2.;
+
+    send_key(&mut app, KeyCode::ArrowDown, ButtonState::Pressed);
+    run_until(&mut app, |app| obstacle_x(app, first) < -(size.x + DINO_DUCK_WIDTH));
+    assert_eq!(state(&app), GameState::Game);
+
+    // letting go stands it back up, too tall for that flyer
+    send_key(&mut app, KeyCode::ArrowDown, ButtonState::Released);
+    let world = app.world_mut();
+    let dino = world.query::<&Dino>().single(world).size();
+    assert!(dino.y > height);
+}
+
+#[test]
+fn menu_game_dead_menu() {
+    let mut app = app();
+    assert_eq!(state(&app), GameState::Menu);
+    assert_eq!(count::<With<Dino>>(&mut app), 0);
+
+    press(&mut app, KeyCode::Space);
+    assert_eq!(state(&app), GameState::Game);
+    assert_eq!(count::<With<Dino>>(&mut app), 1);
+
+    run_until(&mut app, |app| state(app) == GameState::Dead);
+
+    // the first one skips the name for the leaderboard
+    press(&mut app, KeyCode::Escape);
+    assert_eq!(state(&app), GameState::Dead);
+    press(&mut app, KeyCode::Escape);
+    assert_eq!(state(&app), GameState::Menu);
+    assert_eq!(count::<With<Dino>>(&mut app), 0);
+    assert_eq!(count::<With<Obstacle>>(&mut app), 0);
+
+    // and a second run starts from scratch
+    press(&mut app, KeyCode::Space);
+    assert_eq!(state(&app), GameState::Game);
+    assert_eq!(count::<With<Dino>>(&mut app), 1);
+    assert!(app.world().resource::<RunTick>().0 < 5);
+}
+
+// types text the way a keyboard would
+fn type_text(app: &mut App, text: &str) {
+    for c in text.chars() {
+        for state in [ButtonState::Pressed, ButtonState::Released] {
+            app.world_mut().send_event(KeyboardInput {
+                key_code: KeyCode::KeyA,
+                logical_key: Key::Character(c.to_string().into()),
+                state,
+                repeat: false,
+                window: Entity::PLACEHOLDER,
+            });
+            app.update();
+        }
+    }
+}
+
+// a key held down for a few updates, the way a person presses it
+fn hold(app: &mut App, key_code: KeyCode) {
+    send_key(app, key_code, ButtonState::Pressed);
+    for _ in 0..5 {
+        app.update();
+    }
+    send_key(app, key_code, ButtonState::Released);
+}
+
+#[test]
+fn keys_that_close_the_name_entry_do_nothing_else() {
+    let mut app = app();
+    press(&mut app, KeyCode::Space);
+    run_until(&mut app, |app| state(app) == GameState::Dead);
+
+    // enter keeps the name, and doesn't play again
+    type_text(&mut app, "ann");
+    hold(&mut app, KeyCode::Enter);
+    assert_eq!(state(&app), GameState::Dead);
+    assert_eq!(app.world().resource::<SaveData>().file.profile, "ANN");
+
+    // escape skips the name, and doesn't leave
+    press(&mut app, KeyCode::Space);
+    run_until(&mut app, |app| state(app) == GameState::Dead);
+    hold(&mut app, KeyCode::Escape);
+    assert_eq!(state(&app), GameState::Dead);
+
+    // nor quits the game from the menu
+    press(&mut app, KeyCode::Escape);
+    assert_eq!(state(&app), GameState::Menu);
+    click(&mut app, "New profile");
+    send_key(&mut app, KeyCode::Escape, ButtonState::Pressed);
+    for _ in 0..2 {
+        app.update();
+        assert!(app.world().resource::<Events<AppExit>>().is_empty());
+    }
+    send_key(&mut app, KeyCode::Escape, ButtonState::Released);
+    assert_eq!(state(&app), GameState::Menu);
+}
+
+#[test]
+fn top_runs_get_a_name() {
+    let mut app = app();
+    press(&mut app, KeyCode::Space);
+    run_until(&mut app, |app| state(app) == GameState::Dead);
+
+    // typing doesn't play again
+    type_text(&mut app, "ann");
+    press(&mut app, KeyCode::Space);
+    type_text(&mut app, "leex");
+    press(&mut app, KeyCode::Backspace);
+    assert_eq!(state(&app), GameState::Dead);
+    press(&mut app, KeyCode::Enter);
+    assert_eq!(state(&app), GameState::Dead);
+    let save = &app.world().resource::<SaveData>().file;
+    assert_eq!(save.leaderboard[0].name, "ANN LEE");
+    assert_eq!(save.profile, "ANN LEE");
+
+    // the next one is that profile's, the menu goes back to the first
+    press(&mut app, KeyCode::Space);
+    assert_eq!(state(&app), GameState::Game);
+    run_until(&mut app, |app| state(app) == GameState::Dead);
+    press(&mut app, KeyCode::Enter);
+    assert_eq!(app.world().resource::<SaveData>().file.leaderboard[1].name, "ANN LEE");
+    press(&mut app, KeyCode::Escape);
+    assert_eq!(state(&app), GameState::Menu);
+    click(&mut app, "Profile: ANN LEE");
+    app.update();
+    assert_eq!(app.world().resource::<SaveData>().file.profile, chromedino::score::DEFAULT_PROFILE);
+
+    click(&mut app, "Leaderboard");
+    app.update();
+    assert_eq!(state(&app), GameState::Leaderboard);
+    app.update();
+    let world = app.world_mut();
+    assert_eq!(world.query::<&Text>().iter(world).filter(|text| text.0 == "ANN LEE").count(), 2);
+}
+
+#[test]
+fn pause_freezes_the_run() {
+    let mut app = app();
+    press(&mut app, KeyCode::Space);
+    for _ in 0..30 {
+        app.update();
+    }
+
+    press(&mut app, KeyCode::KeyP);
+    assert_eq!(run_state(&app), RunState::Paused);
+    let first = next_obstacle(&mut app);
+    let (tick, x) = (run_tick(&app), obstacle_x(&app, first));
+    for _ in 0..TICKS_PER_SECOND as u32 {
+        app.update();
+    }
+    assert_eq!(run_tick(&app), tick);
+    assert_eq!(obstacle_x(&app, first), x);
+
+    // picks up where it stopped
+    press(&mut app, KeyCode::Escape);
+    assert_eq!(run_state(&app), RunState::Running);
+    assert_eq!(state(&app), GameState::Game);
+    app.update();
+    assert_eq!(run_tick(&app), tick + 1);
+    assert!(obstacle_x(&app, first) < x);
+}
+
+#[test]
+fn losing_focus_pauses() {
+    let mut app = app();
+    press(&mut app, KeyCode::Space);
+
+    app.world_mut().send_event(WindowFocused {
+        window: Entity::PLACEHOLDER,
+        focused: false,
+    });
+    app.update();
+    app.update();
+    assert_eq!(run_state(&app), RunState::Paused);
+}
+
+#[test]
+fn pause_menu_buttons() {
+    let mut app = app();
+    press(&mut app, KeyCode::Space);
+    // the same seed lays out the same start again
+    let obstacles = count::<With<Obstacle>>(&mut app);
+    for _ in 0..TICKS_PER_SECOND as u32 {
+        app.update();
+    }
+
+    press(&mut app, KeyCode::Escape);
+    click(&mut app, "Restart");
+    app.update();
+    assert_eq!(run_state(&app), RunState::Running);
+    assert!(run_tick(&app) < 5);
+    assert_eq!(count::<With<Dino>>(&mut app), 1);
+    assert_eq!(count::<With<Obstacle>>(&mut app), obstacles);
+
+    press(&mut app, KeyCode::Escape);
+    click(&mut app, "Main Menu");
+    app.update();
+    assert_eq!(state(&app), GameState::Menu);
+    assert_eq!(count::<With<Dino>>(&mut app), 0);
+    assert_eq!(count::<With<Obstacle>>(&mut app), 0);
+    assert!(!app.world().resource::<Time<Virtual>>().is_paused());
+}
+
+#[test]
+fn hud_counts_up_and_flashes_milestones() {
+    let mut app = app();
+    press(&mut app, KeyCode::Space);
+    assert_eq!(hud_score(&mut app), "HI 00000  00000");
+    for _ in 0..TICKS_PER_SECOND as u32 {
+        app.update();
+    }
+    let score = app.world().resource::<RunScore>().score();
+    assert!(score > 0);
+    assert!(hud_score(&mut app).ends_with(&format!("{score:05}")));
+
+    // jump straight past 100 and the 100 gets shown instead of the score for a while
+    while app.world().resource::<RunScore>().score() < 120 {
+        app.world_mut().resource_mut::<RunScore>().distance += 100.;
+    }
+    app.update();
+    assert_eq!(hud_score(&mut app), "HI 00120  00100");
+    for _ in 0..(TICKS_PER_SECOND * 1.1) as u32 {
+        app.update();
+    }
+    assert!(!hud_score(&mut app).ends_with("00100"));
+}
+
+#[test]
+fn camera_follows_the_timeline() {
+    let mut app = app();
+    let step = |seconds, phase, rotations| CameraStep {
+        seconds,
+        phase,
+        rotations,
+        easing: EaseFunction::Linear,
+    };
+    app.world_mut().resource_mut::<Tuning>().camera.timeline = vec![
+        step(0.5, CameraRotationPhase::Normal, 0.),
+        step(1., CameraRotationPhase::ChangeDirectionWhenTimer, 0.25),
+        step(1., CameraRotationPhase::Normal, 0.25),
+    ];
+    press(&mut app, KeyCode::Space);
+
+    let angle = camera_angle;
+    let wait = |app: &mut App, seconds: f64| {
+        for _ in 0..(seconds * TICKS_PER_SECOND) as u32 {
+            app.update();
+        }
+    };
+
+    wait(&mut app, 0.4);
+    assert_eq!(angle(&mut app), 0.);
+    // halfway through a quarter turn
+    wait(&mut app, 0.6);
+    assert!((angle(&mut app) - std::f32::consts::PI / 4.).abs() < 0.05);
+    // the direction flipped at the end of that step, so it turns back to upright
+    wait(&mut app, 1.5);
+    assert!(angle(&mut app).abs() < 0.05);
+}
+
+#[test]
+fn rule_agent_gets_to_full_speed() {
+    for mode in GameMode::ALL {
+        let mut app = app();
+        *app.world_mut().resource_mut::<GameMode>() = mode;
+        app.world_mut().resource_mut::<Autopilot>().agent = Some(Box::new(RuleAgent));
+        press(&mut app, KeyCode::Space);
+        run_until(&mut app, |app| {
+            assert_eq!(state(app), GameState::Game, "died in {}", mode.name());
+            app.world().resource::<RunScore>().seconds >= 60.
+        });
+    }
+}
+
+fn camera_angle(app: &mut App) -> f32 {
+    let world = app.world_mut();
+    // the world camera, the ui one never turns
+    let (_, transform) = world
+        .query::<(&Camera, &Transform)>()
+        .iter(world)
+        .find(|(camera, _)| camera.order == 0)
+        .unwrap();
+    transform.rotation.to_euler(EulerRot::XYZ).2
+}
+
+// the rule agent for a while, then nothing: decided per tick, so it's the same input at any frame rate
+struct GivesUpAt(u32);
+impl Agent for GivesUpAt {
+    fn name(&self) -> &str {
+        "gives up"
+    }
+
+    fn act(&mut self, view: &AgentView, tuning: &Tuning) -> DinoInput {
+        if view.tick < self.0 {
+            RuleAgent.act(view, tuning)
+        } else {
+            DinoInput::default()
+        }
+    }
+}
+
+// where the run ended: the dino, how far it got, on which tick, and how the camera was turned
+fn run_at(frame: Duration) -> (Vec2, f32, u32, f32) {
+    let mut app = app_at(frame);
+    let step = |seconds, phase, rotations| CameraStep {
+        seconds,
+        phase,
+        rotations,
+        easing: EaseFunction::CubicInOut,
+    };
+    app.world_mut().resource_mut::<Tuning>().camera.timeline = vec![
+        step(1., CameraRotationPhase::Normal, 0.3),
+        step(2., CameraRotationPhase::ChangeDirectionWhenTimer, 0.5),
+        step(1., CameraRotationPhase::ChangeDirectionWhenJump, 0.7),
+    ];
+    app.world_mut().resource_mut::<Autopilot>().agent = Some(Box::new(GivesUpAt(TICKS_PER_SECOND as u32 * 8)));
+    press(&mut app, KeyCode::Space);
+    run_until(&mut app, |app| state(app) == GameState::Dead);
+
+    let world = app.world_mut();
+    let position = world.query_filtered::<&Position, With<Dino>>().single(world).0;
+    let distance = app.world().resource::<RunScore>().distance;
+    (position, distance, run_tick(&app), camera_angle(&mut app))
+}
+
+#[test]
+fn runs_play_the_same_at_any_frame_rate() {
+    let (position, distance, tick, angle) = run_at(Duration::from_secs_f64(1. / TICKS_PER_SECOND));
+    // past the agent, and with the camera turned
+    assert!(tick > TICKS_PER_SECOND as u32 * 8);
+    assert!(angle.abs() > 0.1);
+    for frames_per_second in [60., 30.] {
+        let (other_position, other_distance, other_tick, other_angle) =
+            run_at(Duration::from_secs_f64(1. / frames_per_second));
+        assert_eq!(other_tick, tick, "at {frames_per_second} fps");
+        assert!(other_position.distance(position) < 1e-3, "at {frames_per_second} fps");
+        assert!((other_distance - distance).abs() < 1e-3, "at {frames_per_second} fps");
+        assert!((other_angle - angle).abs() < 1e-4, "at {frames_per_second} fps");
+    }
+}
+
+// a run with a bit of everything in it: the agent jumps, holds and ducks for a while, then leaves the dino to die
+fn play_a_run(app: &mut App) {
+    app.world_mut().resource_mut::<Autopilot>().agent = Some(Box::new(RuleAgent));
+    press(app, KeyCode::Space);
+    for _ in 0..TICKS_PER_SECOND as u32 * 10 {
+        app.update();
+    }
+    app.world_mut().resource_mut::<Autopilot>().agent = None;
+    run_until(app, |app| state(app) != GameState::Game);
+}
+
+// the way the menu's button plays it, from the file the run was saved to
+fn watch_last_run(app: &mut App, name: &str) {
+    let dir = std::env::temp_dir().join(format!("chromedino-test-{}-{name}", std::process::id()));
+    let path = dir.join("last.replay");
+    app.world().resource::<Recording>().save(&path).unwrap();
+    app.world_mut().insert_resource(ReplaySource(Some(path)));
+    // skips the name, then back to the menu
+    press(app, KeyCode::Escape);
+    press(app, KeyCode::Escape);
+    assert_eq!(state(app), GameState::Menu);
+    click(app, "Watch replay");
+    app.update();
+    assert_eq!(state(app), GameState::Replay);
+}
+
+#[test]
+fn replay_plays_back_to_the_same_death() {
+    let mut app = app();
+    play_a_run(&mut app);
+    assert_eq!(state(&app), GameState::Dead);
+    let (ticks, distance) = (run_tick(&app), app.world().resource::<RunScore>().distance);
+    assert!(!app.world().resource::<Recording>().jumps.is_empty());
+
+    watch_last_run(&mut app, "replay");
+    run_until(&mut app, |app| state(app) == GameState::Dead);
+    assert_eq!(run_tick(&app), ticks);
+    assert_eq!(app.world().resource::<RunScore>().distance, distance);
+    assert!(app.world().resource::<Events<ReplayDesync>>().is_empty());
+}
+
+fn obstacle_positions(app: &mut App) -> Vec<(i32, i32)> {
+    let world = app.world_mut();
+    let mut positions: Vec<_> = world
+        .query_filtered::<&Position, With<Obstacle>>()
+        .iter(world)
+        .map(|position| (position.x.round() as i32, position.y.round() as i32))
+        .collect();
+    positions.sort();
+    positions
+}
+
+#[test]
+fn restarted_replay_plays_the_same() {
+    let mut app = app();
+    // a seed of its own, not the fixed one every run gets
+    app.world_mut().resource_mut::<GameRng>().fixed_seed = None;
+    play_a_run(&mut app);
+    let ticks = run_tick(&app);
+
+    watch_last_run(&mut app, "restart");
+    run_until(&mut app, |app| run_tick(app) == 60);
+    let obstacles = obstacle_positions(&mut app);
+    run_until(&mut app, |app| run_tick(app) == 240);
+    press(&mut app, KeyCode::Escape);
+    click(&mut app, "Restart");
+    app.update();
+    assert_eq!(state(&app), GameState::Replay);
+    run_until(&mut app, |app| run_tick(app) == 60);
+    assert_eq!(obstacle_positions(&mut app), obstacles);
+
+    run_until(&mut app, |app| state(app) == GameState::Dead);
+    assert_eq!(run_tick(&app), ticks);
+    assert!(app.world().resource::<Events<ReplayDesync>>().is_empty());
+}
+
+#[test]
+fn demo_plays_behind_the_menu() {
+    let mut app = app();
+    run_until(&mut app, |app| state(app) == GameState::Demo);
+    for _ in 0..TICKS_PER_SECOND as u32 * 5 {
+        app.update();
+    }
+    assert_eq!(state(&app), GameState::Demo);
+    assert!(run_tick(&app) > 0);
+
+    // any key goes back, and nothing from the demo is left
+    press(&mut app, KeyCode::KeyA);
+    assert_eq!(state(&app), GameState::Menu);
+    assert_eq!(count::<With<Dino>>(&mut app), 0);
+    assert_eq!(count::<With<Obstacle>>(&mut app), 0);
+}
+
+#[test]
+fn rebound_jump() {
+    let mut app = app();
+    click(&mut app, "Controls");
+    app.update();
+    assert_eq!(state(&app), GameState::Controls);
+    click(&mut app, "Jump");
+    press(&mut app, KeyCode::KeyW);
+    press(&mut app, KeyCode::Escape);
+    assert_eq!(state(&app), GameState::Menu);
+
+    // enter confirms too, space isn't jump anymore but w is
+    press(&mut app, KeyCode::Enter);
+    assert_eq!(state(&app), GameState::Game);
+    press(&mut app, KeyCode::Space);
+    assert_eq!(dino(&mut app).0, 0.);
+    press(&mut app, KeyCode::KeyW);
+    assert!(dino(&mut app).0 > 0.);
+}
+
+#[test]
+fn camera_rotation_can_be_turned_off() {
+    let mut app = app();
+    app.world_mut().resource_mut::<Tuning>().camera.timeline = vec![CameraStep {
+        seconds: 1.,
+        rotations: 1.,
+        ..default()
+    }];
+    click(&mut app, "Settings");
+    app.update();
+    assert_eq!(state(&app), GameState::Settings);
+    click(&mut app, "Camera rotation");
+    assert!(!app.world().resource::<Settings>().file.camera_rotation);
+    press(&mut app, KeyCode::Escape);
+    assert_eq!(state(&app), GameState::Menu);
+
+    press(&mut app, KeyCode::Space);
+    for _ in 0..TICKS_PER_SECOND as u32 / 2 {
+        app.update();
+        let world = app.world_mut();
+        let (_, transform) = world
+            .query::<(&Camera, &Transform)>()
+            .iter(world)
+            .find(|(camera, _)| camera.order == 0)
+            .unwrap();
+        assert_eq!(transform.rotation, Quat::IDENTITY);
+    }
+}
+
+#[test]
+fn jumping_and_dying_make_sounds() {
+    let mut app = app();
+    press(&mut app, KeyCode::Space);
+    // events only last two updates, so they're collected after every one
+    let drain = |app: &mut App| app.world_mut().resource_mut::<Events<Sound>>().drain().collect::<Vec<_>>();
+    drain(&mut app);
+
+    send_key(&mut app, KeyCode::Space, ButtonState::Pressed);
+    let mut heard = drain(&mut app);
+    send_key(&mut app, KeyCode::Space, ButtonState::Released);
+    heard.extend(drain(&mut app));
+    for _ in 0..TICKS_PER_SECOND as u32 {
+        app.update();
+        heard.extend(drain(&mut app));
+    }
+    press(&mut app, KeyCode::KeyQ);
+    app.update();
+    heard.extend(drain(&mut app));
+    assert_eq!(heard, [Sound::Jump, Sound::Land, Sound::Death]);
+}
@@ -0,0 +1,75 @@
+// the environment api, the way a training script uses it
+
+use chromedino::gym::{GymAction, DinoEnv, EnvConfig, Request, NEXT_OBSTACLES};
+
+const SEED: u64 = 7;
+const MAX_STEPS: u32 = 60 * 120;
+
+fn run_to_the_end(env: &mut DinoEnv, action: GymAction) -> (Vec<f32>, u32) {
+    let mut rewards = Vec::new();
+    for steps in 1..=MAX_STEPS {
+        let step = env.step(action);
+        rewards.push(step.reward);
+        if step.done {
+            return (rewards, steps);
+        }
+    }
+    panic!("still alive after {MAX_STEPS} steps");
+}
+
+#[test]
+fn reset_observes_the_start() {
+    let mut env = DinoEnv::new(EnvConfig::default());
+    let observation = env.reset(SEED);
+    assert_eq!(observation.height, 0.);
+    assert!(observation.game_speed < 0.01);
+    assert!(observation.obstacles[0].distance > 0.);
+    assert!(observation.obstacles[0].width > 0.);
+    assert_eq!(observation.to_vec().len(), 4 + 4 * NEXT_OBSTACLES + 2);
+}
+
+#[test]
+fn running_into_the_first_obstacle_ends_the_episode() {
+    let mut env = DinoEnv::new(EnvConfig::default());
+    env.reset(SEED);
+    let (rewards, _) = run_to_the_end(&mut env, GymAction::Run);
+    assert!(*rewards.last().unwrap() < 0.);
+    assert!(rewards.iter().take(rewards.len() - 1).all(|&reward| reward >= 0.));
+    assert!(rewards.iter().sum::<f32>() > -100.);
+
+    // done stays done until the next reset
+    let step = env.step(GymAction::Jump);
+    assert!(step.done && step.reward == 0.);
+    assert!(!env.reset(SEED).ducking);
+    assert!(!env.step(GymAction::Run).done);
+}
+
+#[test]
+fn same_seed_same_episode() {
+    let mut env = DinoEnv::new(EnvConfig { ticks_per_step: 4, ..Default::default() });
+    env.reset(SEED);
+    let first = run_to_the_end(&mut env, GymAction::Run);
+    env.reset(SEED);
+    assert_eq!(run_to_the_end(&mut env, GymAction::Run), first);
+}
+
+#[test]
+fn jumping_leaves_the_ground() {
+    let mut env = DinoEnv::new(EnvConfig::default());
+    env.reset(SEED);
+    let mut highest = 0f32;
+    for _ in 0..30 {
+        highest = highest.max(env.step(GymAction::Jump).observation.height);
+    }
+    assert!(highest > 50.);
+    // letting go cuts the jump short
+    assert!(env.step(GymAction::Run).observation.velocity <= 300.);
+}
+
+#[test]
+fn requests_are_json_lines() {
+    let parse = |line: &str| serde_json::from_str::<Request>(line).unwrap();
+    assert_eq!(parse(r#"{"reset": {"seed": 3}}"#), Request::Reset { seed: 3 });
+    assert_eq!(parse(r#"{"step": {"action": "duck"}}"#), Request::Step { action: GymAction::Duck });
+    assert_eq!(parse(r#""close""#), Request::Close);
+}